@@ -0,0 +1,31 @@
+//! Benchmarks comparing the approximators on a fixed set of mirrors and figures. The number of
+//! points found by each method is reported as its throughput (and printed beforehand).
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+mod cases;
+
+use crate::cases::{Inputs, CASES, METHODS, STEPS};
+
+fn approximators(c: &mut Criterion) {
+    for case in &CASES {
+        let mut group = c.benchmark_group(case.name);
+        for &step in &STEPS {
+            let inputs = Inputs::new(case, step);
+            for &(method, threshold) in &METHODS {
+                let points = inputs.reflect(method, threshold);
+                println!("{}/{:?}/{}: {} points", case.name, method, step, points);
+                group.throughput(Throughput::Elements(points as u64));
+                group.bench_with_input(
+                    BenchmarkId::new(format!("{:?}", method), step),
+                    &inputs,
+                    |b, inputs| b.iter(|| inputs.reflect(method, threshold)),
+                );
+            }
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, approximators);
+criterion_main!(benches);
@@ -0,0 +1,92 @@
+//! The inputs on which the approximators are benchmarked. These are shared with the tests, which
+//! check that every method finds a reflection for each of them.
+
+use std::collections::HashMap;
+
+use reflections::approximation::{Equation, Interval, MirrorSampling, View};
+use reflections::reflectors::ReflectionInputs;
+use reflections::spatial::Point2D;
+use reflections::{from_spec, EquationSpec, Method};
+
+/// A mirror and a figure to reflect in it, each given as `x(t)` and `y(t)`.
+pub struct Case {
+    pub name: &'static str,
+    pub mirror: [&'static str; 2],
+    pub figure: [&'static str; 2],
+}
+
+pub const CASES: [Case; 4] = [
+    Case { name: "line", mirror: ["t", "0"], figure: ["t", "1"] },
+    Case { name: "parabola", mirror: ["t", "t^2 / 4"], figure: ["t", "2"] },
+    Case { name: "circle", mirror: ["2 * cos(t)", "2 * sin(t)"], figure: ["t", "sin(t)"] },
+    Case { name: "sine", mirror: ["t", "sin(t)"], figure: ["sin(2 * t)", "t"] },
+];
+
+/// The steps with which `t` and `s` are sampled: the smaller the step, the more samples.
+pub const STEPS: [f64; 2] = [0.1, 0.02];
+
+/// Each method, paired with a suitable threshold.
+pub const METHODS: [(Method, f64); 3] = [
+    (Method::Rasterisation, 1.0),
+    (Method::Linear, 0.01),
+    (Method::Quadratic, 0.0),
+];
+
+/// The equations, intervals and view with which to approximate the reflection for a case.
+pub struct Inputs {
+    pub mirror: Equation<'static, f64>,
+    pub figure: Vec<Point2D>,
+    pub sigma_tau: Equation<'static, (f64, f64)>,
+    pub t_interval: Interval,
+    pub s_interval: Interval,
+    pub view: View,
+}
+
+impl Inputs {
+    /// The inputs for the ordinary reflection of the `case`, with `t` and `s` sampled over
+    /// `[-4, 4]` with the given `step`.
+    pub fn new(case: &Case, step: f64) -> Inputs {
+        thread_local! {
+            // None of the equations have any variables other than the parameters, so every case
+            // shares the same empty bindings. A `HashMap` cannot be constructed in a `static`, so
+            // the bindings are leaked (once per thread) to be borrowed for `'static`.
+            static BINDINGS: &'static HashMap<char, f64> = Box::leak(Box::new(HashMap::new()));
+        }
+        let bindings = BINDINGS.with(|bindings| *bindings);
+        let spec = |[x, y]: [&str; 2]| EquationSpec { x: x.to_string(), y: y.to_string() };
+        let equation = |string| {
+            from_spec(&spec(string), &['t'], bindings, |bindings, t| {
+                bindings.insert('t', t);
+            }).unwrap()
+        };
+        let sigma_tau = from_spec(&spec(["-s", "t"]), &['s', 't'], bindings, |bindings, (s, t)| {
+            bindings.insert('s', s);
+            bindings.insert('t', t);
+        }).unwrap();
+
+        let interval = Interval::new(-4.0, 4.0, step);
+        let (t_interval, s_interval) = (interval.clone(), interval);
+        let (mirror, figure) = (equation(case.mirror), equation(case.figure));
+        let figure = figure.sample(&t_interval);
+        let view = View::fit(&[Point2D::diag(-6.0), Point2D::diag(6.0)], 256, 256, 0.0);
+
+        Inputs { mirror, figure, sigma_tau, t_interval, s_interval, view }
+    }
+
+    /// The inputs in the form expected by the approximators.
+    pub fn reflection_inputs(&self) -> ReflectionInputs<'_> {
+        ReflectionInputs {
+            mirror: &self.mirror,
+            figure: &self.figure,
+            sigma_tau: &self.sigma_tau,
+            t_interval: &self.t_interval,
+            s_interval: &self.s_interval,
+        }
+    }
+
+    /// Approximate the reflection using the given method, returning the number of points found.
+    pub fn reflect(&self, method: Method, threshold: f64) -> usize {
+        let approximator = method.approximator(threshold, MirrorSampling::Uniform);
+        approximator.approximate_reflection(self.reflection_inputs(), &self.view, None).len()
+    }
+}
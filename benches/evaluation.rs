@@ -0,0 +1,40 @@
+//! Benchmarks comparing evaluating an expression at each point in turn, each with its own map of
+//! bindings, against evaluating it in a batch, which reuses a single map.
+
+use std::collections::HashMap;
+use std::iter;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use reflections::parser::{Expr, Lexer, Parser};
+
+/// The expressions to evaluate, in the variable `t`.
+const EXPRESSIONS: [&str; 2] = ["sin(t)", "t^2 / 4 + 2 * cos(3 * t)"];
+
+fn parse(string: &str) -> Expr {
+    let lexemes = Lexer::scan(string.chars()).unwrap();
+    Parser::new(Lexer::evaluate(lexemes.into_iter()).collect()).parse().unwrap()
+}
+
+fn evaluation(c: &mut Criterion) {
+    let static_bindings = HashMap::new();
+    let ts: Vec<_> = (0..1000).map(|i| i as f64 / 100.0).collect();
+    let mut group = c.benchmark_group("evaluation");
+    group.throughput(Throughput::Elements(ts.len() as u64));
+    for &string in &EXPRESSIONS {
+        let expr = parse(string);
+        group.bench_with_input(BenchmarkId::new("pointwise", string), &expr, |b, expr| {
+            b.iter(|| ts.iter().map(|&t| {
+                let bindings = iter::once(('t', t)).collect();
+                expr.evaluate((&bindings, &static_bindings))
+            }).collect::<Vec<_>>())
+        });
+        group.bench_with_input(BenchmarkId::new("batch", string), &expr, |b, expr| {
+            b.iter(|| expr.evaluate_batch('t', &ts, &static_bindings))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, evaluation);
+criterion_main!(benches);
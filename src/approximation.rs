@@ -1,35 +1,208 @@
 use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::f64;
+use std::fmt;
+use std::iter;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
 
-use crate::spatial::Point2D;
+use crate::parser::{self, Expr, ParseError};
+use crate::sampling::{adaptive_sample, Angle, KeyValue};
+use crate::spatial::{Pair, Point2D, Scalar};
 
 /// A closed interval; essentially a floating-point `RangeInclusive` with some convenience methods.
-#[derive(Clone)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Interval {
     pub start: f64,
     pub end: f64,
     pub step: f64,
+    /// Whether the interval wraps around, so that `end` is identified with `start`, as for the
+    /// parameter of a closed curve. A periodic interval never yields `end`, which would duplicate
+    /// `start`.
+    #[serde(default)]
+    pub periodic: bool,
 }
 
+/// The distance (as a proportion of the step) within which a sample is considered to coincide with
+/// the end of an interval. This absorbs floating-point error in the step, so that the final sample
+/// is `end` exactly.
+const END_TOLERANCE: f64 = 1e-6;
+
 impl Interval {
+    /// An interval from `start` to `end`, sampled every `step`.
+    pub fn new(start: f64, end: f64, step: f64) -> Self {
+        Interval { start, end, step, periodic: false }
+    }
+
+    /// A periodic interval, whose `end` is identified with its `start`.
+    pub fn periodic(start: f64, end: f64, step: f64) -> Self {
+        Interval { periodic: true, ..Interval::new(start, end, step) }
+    }
+
+    /// The length of the period of a periodic interval.
+    pub fn period(&self) -> f64 {
+        self.end - self.start
+    }
+
     pub fn endpoints(start: f64, end: f64) -> Self {
-        Interval { start, end, step: end - start }
+        Interval::new(start, end, end - start)
+    }
+
+    /// An interval of `n` evenly-spaced samples, the first of which is `start` and the last of
+    /// which is `end`. An interval always includes both endpoints, and coincident samples are only
+    /// yielded once, so `None` is returned if `n` is less than 2, or if the width of the interval
+    /// is not positive and finite.
+    pub fn with_samples(start: f64, end: f64, n: usize) -> Option<Self> {
+        let width = end - start;
+        if n < 2 || !width.is_finite() || width <= 0.0 {
+            return None;
+        }
+        Some(Interval::new(start, end, width / (n - 1) as f64))
+    }
+
+    /// Whether `t` lies within the interval, including its endpoints. This is false for NaN.
+    pub fn contains(&self, t: f64) -> bool {
+        t >= self.start && t <= self.end
+    }
+
+    /// The nearest value to `t` within the interval. NaN is returned unchanged.
+    pub fn clamp(&self, t: f64) -> f64 {
+        if t < self.start {
+            self.start
+        } else if t > self.end {
+            self.end
+        } else {
+            t
+        }
+    }
+
+    /// The distance within which a sample is considered to coincide with `end`. A step that is not
+    /// finite cannot be used to sample the interval, so has no tolerance.
+    fn tolerance(&self) -> f64 {
+        if self.step.is_finite() {
+            (self.step * END_TOLERANCE).max(0.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Iterate over the samples of the interval, from `start` to `end` in increments of `step`.
+    pub fn iter(&self) -> IntervalIter<'_> {
+        IntervalIter { interval: self, index: 0 }
+    }
+
+    /// The number of samples yielded by `iter`, computed without iterating over them, so that it is
+    /// cheap even for intervals with vast numbers of samples.
+    pub fn sample_count(&self) -> u64 {
+        let Interval { start, end, step, periodic } = *self;
+        let tolerance = self.tolerance();
+        // These conditions mirror those in `IntervalIter::next`.
+        if start.is_nan() || end.is_nan() || start > end + tolerance {
+            return 0;
+        }
+        let sample = |i: u64| start + i as f64 * step;
+        if !step.is_finite() || sample(1) <= start {
+            return 1;
+        }
+
+        // The index of the last sample, estimated by division and then corrected for rounding, as
+        // the samples are computed by multiplication.
+        let mut last = ((end + tolerance - start) / step).floor() as u64;
+        while last > 0 && sample(last) > end + tolerance {
+            last -= 1;
+        }
+        while last < u64::MAX && sample(last + 1) <= end + tolerance {
+            last += 1;
+        }
+        if periodic && last > 0 && (end - sample(last)).abs() <= tolerance {
+            last
+        } else {
+            // The count saturates for intervals with more than `u64::MAX` samples.
+            last.saturating_add(1)
+        }
+    }
+
+    /// The number of samples yielded by `iter`, as a `usize`. This saturates at `usize::MAX`.
+    pub fn len(&self) -> usize {
+        self.sample_count().min(usize::MAX as u64) as usize
+    }
+
+    /// Whether the interval has no samples (e.g. because `start` is greater than `end`).
+    pub fn is_empty(&self) -> bool {
+        self.sample_count() == 0
     }
 }
 
-impl Iterator for Interval {
+impl<'a> IntoIterator for &'a Interval {
+    type Item = f64;
+    type IntoIter = IntervalIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the samples of an `Interval`, returned by `Interval::iter`.
+#[derive(Clone, Debug)]
+pub struct IntervalIter<'a> {
+    interval: &'a Interval,
+    /// The number of samples that have been yielded so far.
+    index: u64,
+}
+
+impl Iterator for IntervalIter<'_> {
     type Item = f64;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.start > self.end {
+        let Interval { start, end, step, periodic } = *self.interval;
+        let tolerance = self.interval.tolerance();
+        // Each sample is computed directly from `start`, rather than by accumulating the step, so
+        // that floating-point error doesn't build up over the course of the interval.
+        let t = match self.index {
+            0 => start,
+            i => start + i as f64 * step,
+        };
+
+        // A NaN endpoint fails every comparison, producing an empty interval. A step that is not
+        // positive and finite would never reach the end of the interval, nor would a step too small
+        // to affect `start`, so in those cases we yield only the first point.
+        if t <= end + tolerance && (self.index == 0 || step.is_finite() && t > start) {
+            self.index += 1;
+            if (end - t).abs() <= tolerance {
+                if periodic && self.index > 1 {
+                    None
+                } else {
+                    Some(end)
+                }
+            } else {
+                Some(t)
+            }
+        } else {
             None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // `index` exceeds the number of samples if the iterator skipped the end of a periodic
+        // interval, so the subtraction must saturate. A saturated count is only a lower bound.
+        let remaining = self.interval.sample_count().saturating_sub(self.index);
+        if remaining < usize::MAX as u64 {
+            (remaining as usize, Some(remaining as usize))
         } else {
-            let start = self.start;
-            self.start += self.step;
-            Some(start)
+            (usize::MAX, None)
         }
     }
 }
 
+impl fmt::Display for Interval {
+    /// An interval is displayed as `[start, end] step=step`, or `[start, end)` if it is periodic,
+    /// as `end` is then not sampled.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let close = if self.periodic { ")" } else { "]" };
+        write!(f, "[{}, {}{} step={}", self.start, self.end, close, self.step)
+    }
+}
+
 /// An `f64` that implements `Ord`, when we don't care about NaNs. Specifically, `OrdFloat` is
 /// ordered as `f64`, but treats all NaNs as being equal and less than any other value.
 #[derive(Clone, Copy, Debug)]
@@ -60,7 +233,9 @@ impl Eq for OrdFloat {}
 
 impl PartialOrd for OrdFloat {
     fn partial_cmp(&self, other: &OrdFloat) -> Option<Ordering> {
-        self.0.partial_cmp(&other.0)
+        // This must agree with `Ord` (including for NaNs), which collections like `BinaryHeap`
+        // rely upon.
+        Some(self.cmp(other))
     }
 }
 
@@ -81,38 +256,432 @@ impl From<OrdFloat> for f64 {
     }
 }
 
-/// A parametric equation ℝ × ℝ → ℝ × ℝ.
-pub struct Equation<'a, I> {
-    pub function: Box<dyn 'a + Fn(I) -> Point2D>,
+/// A bound satisfied by thread-safe types when the `parallel` feature is enabled, and by every type
+/// otherwise, so that closures need only be thread-safe when they may be called in parallel.
+#[cfg(feature = "parallel")]
+pub trait MaybeSendSync: Send + Sync {}
+#[cfg(feature = "parallel")]
+impl<T: ?Sized + Send + Sync> MaybeSendSync for T {}
+#[cfg(not(feature = "parallel"))]
+pub trait MaybeSendSync {}
+#[cfg(not(feature = "parallel"))]
+impl<T: ?Sized> MaybeSendSync for T {}
+
+/// A boxed function, as stored in an `Equation`, which is thread-safe when the `parallel` feature
+/// is enabled.
+#[cfg(feature = "parallel")]
+pub type BoxFn<'a, I, O> = Box<dyn 'a + Fn(I) -> O + Send + Sync>;
+#[cfg(not(feature = "parallel"))]
+pub type BoxFn<'a, I, O> = Box<dyn 'a + Fn(I) -> O>;
+
+/// A boxed function evaluating an `Equation` at many inputs at once, like `BoxFn`.
+#[cfg(feature = "parallel")]
+pub type BoxBatchFn<'a, I, O> = Box<dyn 'a + Fn(&[I]) -> Vec<O> + Send + Sync>;
+#[cfg(not(feature = "parallel"))]
+pub type BoxBatchFn<'a, I, O> = Box<dyn 'a + Fn(&[I]) -> Vec<O>>;
+
+/// A parametric equation ℝ × ℝ → ℝ × ℝ. The output may instead be three-dimensional (e.g. to
+/// describe a space curve), though most of the functionality is specific to two dimensions.
+///
+/// With the `parallel` feature, the function is required to be thread-safe so that equations may
+/// be sampled in parallel.
+pub struct Equation<'a, I, O = Point2D> {
+    pub function: BoxFn<'a, I, O>,
+    /// The exact derivative of `function`, if known. Otherwise, the derivative is approximated
+    /// using finite differences.
+    pub derivative: Option<BoxFn<'a, I, O>>,
+    /// Evaluates `function` at many inputs at once, if this is more efficient than evaluating it
+    /// at each in turn. This is used by `sample`.
+    pub batch: Option<BoxBatchFn<'a, I, O>>,
+    /// The step used to approximate the derivative by finite differences. A smaller step reduces
+    /// the truncation error, but amplifies floating-point error in `function`, as the difference
+    /// between nearby values is divided by the step. This is `DEFAULT_H` unless set otherwise.
+    pub h: f64,
+}
+
+/// The default step used to approximate the derivative of an equation by finite differences.
+pub const DEFAULT_H: f64 = 0.1;
+
+impl<'a, I, O> Equation<'a, I, O> {
+    pub fn new(function: BoxFn<'a, I, O>) -> Self {
+        Equation { function, derivative: None, batch: None, h: DEFAULT_H }
+    }
+
+    /// Set the step used to approximate the derivative by finite differences.
+    pub fn with_h(self, h: f64) -> Self {
+        Equation { h, ..self }
+    }
+
+    /// Supply the exact derivative of the equation, which will be used in preference to a
+    /// finite-difference approximation.
+    pub fn with_derivative(self, derivative: BoxFn<'a, I, O>) -> Self {
+        Equation { derivative: Some(derivative), ..self }
+    }
+
+    /// Supply a function to evaluate the equation at many inputs at once, which will be used in
+    /// preference to evaluating `function` at each input.
+    pub fn with_batch(self, batch: BoxBatchFn<'a, I, O>) -> Self {
+        Equation { batch: Some(batch), ..self }
+    }
+}
+
+impl<'a, I: 'a, O: 'a> Equation<'a, I, O> {
+    /// Compose the equation with a reparameterisation `g`, producing the equation `f(g(p))`. This
+    /// may be used to compose `sigma_tau` with another map as well as to reparameterise a curve.
+    /// The exact derivative, if known, is discarded, as the derivative of `g` is not known.
+    pub fn reparameterise<J: Clone>(
+        self,
+        g: impl 'a + Fn(J) -> I + Copy + MaybeSendSync,
+    ) -> Equation<'a, J, O> {
+        let function = self.function;
+        Equation {
+            function: box move |p| function(g(p)),
+            derivative: None,
+            batch: self.batch.map(|batch| {
+                let batch: BoxBatchFn<'a, J, O> = box move |ps| {
+                    batch(&ps.iter().cloned().map(g).collect::<Vec<_>>())
+                };
+                batch
+            }),
+            h: self.h,
+        }
+    }
+}
+
+impl<'a, I: 'a, S: Scalar> Equation<'a, I, Pair<S>> {
+    /// Translate the equation by `offset`, so that it may be repositioned without being re-parsed.
+    pub fn translate(self, offset: Pair<S>) -> Self {
+        let function = self.function;
+        Equation {
+            function: box move |p| function(p) + offset,
+            derivative: self.derivative,
+            batch: self.batch.map(|batch| {
+                let batch: BoxBatchFn<'a, I, _> = box move |ps| {
+                    batch(ps).into_iter().map(|p| p + offset).collect()
+                };
+                batch
+            }),
+            h: self.h,
+        }
+    }
+
+    /// Scale the equation (about the origin) by `factor` in each direction.
+    pub fn scale(self, factor: Pair<S>) -> Self {
+        self.transform_linear(move |p| p * factor)
+    }
+
+    /// Rotate the equation anticlockwise about the origin by `angle` radians.
+    pub fn rotate(self, angle: f64) -> Self {
+        let (sin, cos) = (S::of(angle.sin()), S::of(angle.cos()));
+        self.transform_linear(move |p| {
+            Pair::new([p.x() * cos - p.y() * sin, p.x() * sin + p.y() * cos])
+        })
+    }
+
+    /// Compose a linear transformation after the equation. As the transformation is linear, the
+    /// exact derivative, if known, is transformed in the same way.
+    fn transform_linear(
+        self,
+        transform: impl 'a + Fn(Pair<S>) -> Pair<S> + Copy + MaybeSendSync,
+    ) -> Self {
+        let function = self.function;
+        Equation {
+            function: box move |p| transform(function(p)),
+            derivative: self.derivative.map(|derivative| {
+                let derivative: BoxFn<'a, I, _> =
+                    box move |p| transform(derivative(p));
+                derivative
+            }),
+            batch: self.batch.map(|batch| {
+                let batch: BoxBatchFn<'a, I, _> = box move |ps| {
+                    batch(ps).into_iter().map(transform).collect()
+                };
+                batch
+            }),
+            h: self.h,
+        }
+    }
+}
+
+impl<'a, O> Equation<'a, f64, O> {
+    /// Sample the equation over an interval. The samples are collected into vectors of exactly
+    /// the right size, as `IntervalIter` knows how many samples remain.
+    pub fn sample(&self, interval: &Interval) -> Vec<O> {
+        match &self.batch {
+            Some(batch) => batch(&interval.iter().collect::<Vec<_>>()),
+            None => interval.iter().map(|t| (self.function)(t)).collect(),
+        }
+    }
+
+    /// Evaluate the equation at each of the values `ts`, in the same order.
+    pub fn sample_at(&self, ts: &[f64]) -> Vec<O> {
+        match &self.batch {
+            Some(batch) => batch(ts),
+            None => ts.iter().map(|&t| (self.function)(t)).collect(),
+        }
+    }
 }
 
 impl<'a> Equation<'a, f64> {
-    /// Sample the equation over an interval.
-    pub fn sample(&self, interval: &Interval) -> Vec<Point2D> {
-        interval.clone().map(|t| (self.function)(t)).collect()
+    /// Parse a parametric equation from the strings corresponding to `x(t)` and `y(t)`. Every
+    /// variable other than `t` must be bound in `bindings`.
+    pub fn parse(x: &str, y: &str, bindings: &'a HashMap<char, f64>) -> Result<Self, ParseError> {
+        let expr = [Arc::new(x.parse::<Expr>()?), Arc::new(y.parse::<Expr>()?)];
+        parser::check_bound([&expr[0], &expr[1]], [x, y], &['t'], bindings)?;
+        Ok(Self::from_exprs(expr, bindings))
+    }
+
+    /// Construct a parametric equation from the expressions `x(t)` and `y(t)`, which may also be
+    /// sampled in batches, avoiding allocating bindings for every point. Every variable other than
+    /// `t` must be bound in `bindings` (see `parser::check_bound`), else evaluation will panic.
+    pub fn from_exprs(expr: [Arc<Expr>; 2], bindings: &'a HashMap<char, f64>) -> Self {
+        let batch = expr.clone();
+        Equation::new(box move |t| {
+            let parameters = iter::once(('t', t)).collect();
+            Point2D::new([
+                expr[0].evaluate((&parameters, bindings)),
+                expr[1].evaluate((&parameters, bindings)),
+            ])
+        }).with_batch(box move |ts| {
+            let xs = batch[0].evaluate_batch('t', ts, bindings);
+            let ys = batch[1].evaluate_batch('t', ts, bindings);
+            xs.into_iter().zip(ys).map(|(x, y)| Point2D::new([x, y])).collect()
+        })
+    }
+
+    /// Sample the equation over a range adaptively. This is `sample_adaptive_euclidean`: to sample
+    /// sharp bends more densely instead, see `sample_tangent_angle`.
+    pub fn sample_adaptive(&self, range: RangeInclusive<f64>, samples: u64) -> Vec<Point2D> {
+        self.sample_adaptive_euclidean(range, samples)
+    }
+
+    /// Sample `samples` points (at least 2) of the curve over a range, starting from its endpoints
+    /// and repeatedly adding a point between the neighbouring samples that are furthest apart by
+    /// Euclidean distance (the `Metric` of `Point2D`), so that regions in which the curve moves
+    /// quickly are sampled more densely. The points are returned in order of `t`.
+    pub fn sample_adaptive_euclidean(
+        &self,
+        range: RangeInclusive<f64>,
+        samples: u64,
+    ) -> Vec<Point2D> {
+        let mut samples = adaptive_sample(|t| {
+            let point = (self.function)(t);
+            KeyValue(point, (OrdFloat(t), point))
+        }, range, samples);
+        samples.sort_by_key(|&(t, _)| t);
+        samples.into_iter().map(|(_, point)| point).collect()
+    }
+}
+
+impl<'a, S: Scalar> Equation<'a, f64, Pair<S>> {
+    /// The gradient `dy/dx` of the curve at the given `t`. This is infinite where the tangent is
+    /// vertical, and NaN where the derivative vanishes, as the tangent is then undefined.
+    pub fn gradient(&self, t: f64) -> f64 {
+        let [dx, dy] = self.derivative(t).into_inner();
+        match (dx == S::zero(), dy == S::zero()) {
+            (true, true) => f64::NAN,
+            // A vertical gradient is taken to be positive, as its sign would otherwise depend only
+            // on the sign of the zero `dx`.
+            (true, false) => f64::INFINITY,
+            _ => (dy / dx).as_f64(),
+        }
+    }
+
+    /// The angle of the tangent to the curve at the given `t`, anticlockwise from the positive
+    /// x-axis, in `[-π, π]`. Unlike `gradient`, this distinguishes the direction of the curve.
+    pub fn tangent_angle(&self, t: f64) -> f64 {
+        let [dx, dy] = self.derivative(t).into_inner();
+        dy.atan2(dx).as_f64()
+    }
+
+    /// Sample the parameter `t` over a range adaptively, bisecting the segments across which the
+    /// angle of the tangent turns the most first, so that the sharpest bends in the curve are
+    /// sampled most densely. The values of `t` are returned in ascending order.
+    pub fn sample_tangent_angle(&self, range: RangeInclusive<f64>, samples: u64) -> Vec<f64> {
+        let mut ts = adaptive_sample(|t| {
+            KeyValue(Angle::new(self.tangent_angle(t)), OrdFloat(t))
+        }, range, samples);
+        ts.sort();
+        ts.into_iter().map(|OrdFloat(t)| t).collect()
+    }
+
+    /// Approximate the length of the curve over the interval, by integrating the speed `|f'(t)|`
+    /// using the trapezoidal rule over the samples of the interval. The error is proportional to
+    /// the square of `interval.step`, so a finer step gives a more accurate length.
+    pub fn arc_length(&self, interval: &Interval) -> f64 {
+        let ts = Self::closed_samples(interval);
+        self.cumulative_arc_length(&ts).last().cloned().unwrap_or(0.0)
+    }
+
+    /// Sample `n` points across the interval that are roughly equidistant along the curve, rather
+    /// than in `t`. The points are those at the values of `t` chosen by
+    /// `sample_arc_uniform_parameters`, in the same order.
+    pub fn sample_arc_uniform(&self, interval: &Interval, n: usize) -> Vec<Pair<S>> {
+        self.sample_at(&self.sample_arc_uniform_parameters(interval, n))
+    }
+
+    /// Choose `n` values of `t` across the interval so that the corresponding points are roughly
+    /// equidistant along the curve, rather than in `t`. The arc length is estimated at the samples
+    /// of the interval and inverted by linear interpolation, so a finer step gives more evenly
+    /// spaced points. The values of `t` (not the points themselves) are returned, in ascending
+    /// order.
+    pub fn sample_arc_uniform_parameters(&self, interval: &Interval, n: usize) -> Vec<f64> {
+        let ts = Self::closed_samples(interval);
+        let lengths = self.cumulative_arc_length(&ts);
+        let total = lengths.last().cloned().unwrap_or(0.0);
+        if n < 2 || total <= 0.0 || total.is_nan() {
+            // There is no length to distribute the samples along, so fall back to spacing in `t`.
+            let step = (interval.end - interval.start) / (n.max(2) - 1) as f64;
+            return (0..n).map(|i| interval.start + step * i as f64).collect();
+        }
+
+        let mut segment = 0;
+        (0..n).map(|i| {
+            let target = total * i as f64 / (n - 1) as f64;
+            while segment + 2 < ts.len() && lengths[segment + 1] < target {
+                segment += 1;
+            }
+            let (l0, l1) = (lengths[segment], lengths[segment + 1]);
+            let (t0, t1) = (ts[segment], ts[segment + 1]);
+            if l1 > l0 {
+                t0 + (t1 - t0) * ((target - l0) / (l1 - l0)).clamp(0.0, 1.0)
+            } else {
+                t0
+            }
+        }).collect()
+    }
+
+    /// The samples of the interval, including its end even if it is not a multiple of the step
+    /// from the start.
+    fn closed_samples(interval: &Interval) -> Vec<f64> {
+        let mut ts: Vec<_> = interval.iter().collect();
+        if let Some(&t) = ts.last() {
+            if t < interval.end {
+                ts.push(interval.end);
+            }
+        }
+        ts
+    }
+
+    /// The approximate arc length from the first of the values `ts` (which must be in ascending
+    /// order) to each of the values, using the trapezoidal rule.
+    fn cumulative_arc_length(&self, ts: &[f64]) -> Vec<f64> {
+        let speed = |t| {
+            let [dx, dy] = self.derivative_accurate(t).into_inner();
+            dx.hypot(dy).as_f64()
+        };
+
+        let mut length = 0.0;
+        let mut lengths = Vec::with_capacity(ts.len());
+        let mut previous: Option<(f64, f64)> = None;
+        for &t in ts {
+            let v = speed(t);
+            if let Some((t0, v0)) = previous {
+                length += (t - t0) * (v0 + v) / 2.0;
+            }
+            lengths.push(length);
+            previous = Some((t, v));
+        }
+        lengths
     }
 
     /// Return a new equation representing the normal at the given `t`.
-    pub fn normal(&self, t: f64) -> Equation<'_, f64> {
+    pub fn normal(&self, t: f64) -> Equation<'_, f64, Pair<S>> {
         let [mx, my] = (self.function)(t).into_inner();
-        let [dx, dy] = self.derivative(t).normalise().into_inner();
+        let [dx, dy] = self.derivative_accurate(t).normalise().into_inner();
 
-        Equation {
-            function: box move |s| {
-                Point2D::new([mx - s * dy, my + s * dx])
-            }
+        Equation::new(box move |s| {
+            let s = S::of(s);
+            Pair::new([mx - s * dy, my + s * dx])
+        })
+    }
+
+    /// Return the gradient vector at the given `t`: i.e. the value of the derivative at `t`. This
+    /// is exact if the equation was given a derivative, and approximate (with step `h`) otherwise.
+    pub fn derivative(&self, t: f64) -> Pair<S> {
+        if let Some(derivative) = &self.derivative {
+            derivative(t)
+        } else {
+            self.derivative_with_h(t, self.h)
         }
     }
 
-    /// Return the gradient vector at the given `t`: i.e. the value of the derivative at `t`.
-    pub fn derivative(&self, t: f64) -> Point2D {
-        // The function approximates the derivative using `(f(t + H) - f(t - H)) / 2 * H`.
-        const H: f64 = 0.1;
+    /// Return the gradient vector at the given `t`, like `derivative`, but using Richardson
+    /// extrapolation of the central differences with steps `h` and `h / 2` when the equation has no
+    /// exact derivative. This reduces the error from O(h²) to O(h⁴), at the cost of twice as many
+    /// evaluations.
+    pub fn derivative_accurate(&self, t: f64) -> Pair<S> {
+        if let Some(derivative) = &self.derivative {
+            derivative(t)
+        } else {
+            let h = self.h;
+            let (coarse, fine) = (self.derivative_with_h(t, h), self.derivative_with_h(t, h / 2.0));
+            (fine * Pair::diag(S::of(4.0)) - coarse) / Pair::diag(S::of(3.0))
+        }
+    }
 
+    /// Approximate the gradient vector at the given `t` using a central difference with step `h`.
+    pub fn derivative_with_h(&self, t: f64, h: f64) -> Pair<S> {
+        // The function approximates the derivative using `(f(t + h) - f(t - h)) / 2 * h`.
         let f = &self.function;
-        let (fp, fm) = (f(t + H), f(t - H));
-        let d = 2.0 * H;
-        (fp - fm) / Point2D::diag(d)
+        let (fp, fm) = (f(t + h), f(t - h));
+        let d = 2.0 * h;
+        (fp - fm) / Pair::diag(S::of(d))
+    }
+
+    /// Return the unit tangent vector at the given `t`, pointing in the direction of increasing
+    /// `t`. This is NaN at stationary points, where the tangent is undefined.
+    pub fn tangent(&self, t: f64) -> Pair<S> {
+        self.derivative_accurate(t).normalise()
+    }
+
+    /// Return the signed curvature at the given `t`: the reciprocal of the radius of the osculating
+    /// circle, which is positive where the curve turns anticlockwise. This is NaN or infinite at
+    /// stationary points.
+    pub fn curvature(&self, t: f64) -> f64 {
+        let (d1, d2) = (self.derivative_accurate(t), self.second_derivative(t));
+        let speed = d1.dot(d1).sqrt();
+        (d1.cross(d2) / (speed * speed * speed)).as_f64()
+    }
+
+    /// If the equation is an affine function of `t` over the `interval`, i.e. a straight line
+    /// traversed at a constant speed, return `(origin, direction)` such that the equation is
+    /// `origin + t * direction`. This is checked at each sample of the interval, up to rounding
+    /// error. Returns `None` if the equation is not affine, or is constant.
+    pub fn affine(&self, interval: &Interval) -> Option<(Pair<S>, Pair<S>)> {
+        let (start, end) = (interval.start, interval.end);
+        if end.partial_cmp(&start) != Some(Ordering::Greater) {
+            return None;
+        }
+        let (first, last) = ((self.function)(start), (self.function)(end));
+        let direction = (last - first) / Pair::diag(S::of(end - start));
+        let origin = first - direction * Pair::diag(S::of(start));
+        if direction.is_nan() || direction.dot(direction) == S::zero() {
+            return None;
+        }
+
+        let uniform = Interval { periodic: false, ..interval.clone() };
+        let affine = uniform.iter().all(|t| {
+            (self.function)(t).approx_eq(origin + direction * Pair::diag(S::of(t)))
+        });
+        if affine { Some((origin, direction)) } else { None }
+    }
+
+    /// Approximate the second derivative at the given `t`, using a central difference of the
+    /// derivative if it is exact, and `(f(t + H) - 2 f(t) + f(t - H)) / H²` otherwise.
+    fn second_derivative(&self, t: f64) -> Pair<S> {
+        // The truncation error of the difference is O(H²), whereas the rounding error is O(ε / H²)
+        // for the machine epsilon ε, so `H = ε^(1/4)` balances the two. This is about `1e-4` for
+        // `f64`, and `2e-2` for `f32`.
+        let h = S::epsilon().as_f64().powf(0.25);
+
+        if let Some(derivative) = &self.derivative {
+            (derivative(t + h) - derivative(t - h)) / Pair::diag(S::of(2.0 * h))
+        } else {
+            let f = &self.function;
+            let (fp, f0, fm) = (f(t + h), f(t), f(t - h));
+            (fp - f0 * Pair::diag(S::of(2.0)) + fm) / Pair::diag(S::of(h * h))
+        }
     }
 }
 
@@ -120,7 +689,7 @@ impl<'a> Equation<'a, f64> {
 /// well as the size (in pixels) of the canvas on which it is displayed.
 ///
 /// The struct `View` mirrors the JavaScript class `View` and should be kept in sync.
-#[derive(Deserialize)]
+#[derive(Debug, Deserialize)]
 pub struct View {
     /// The dimensions of the view canvas in pixels.
     pub width: u16,
@@ -130,9 +699,154 @@ pub struct View {
     /// The scale factor (in powers of 2) of the displayed region. E.g. `scale = 0` means a 1:1
     /// aspect ratio; `scale = 1` means zooming in 2x, etc.
     pub scale: f64,
+    /// How cartesian coördinates are mapped onto the canvas.
+    #[serde(default)]
+    pub scale_mode: ScaleMode,
+}
+
+/// The mapping from cartesian coördinates to the region displayed in a `View`.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScaleMode {
+    /// Coördinates are displayed as they are. This is the default.
+    Linear,
+    /// Each coördinate is displayed on a symmetric logarithmic scale (see `signed_log`), which is
+    /// useful for figures spanning several orders of magnitude. The `origin` of the view is still
+    /// given in cartesian coördinates (and is transformed like any other point), but the `scale`
+    /// is interpreted with respect to the transformed coördinates.
+    Logarithmic,
+}
+
+impl Default for ScaleMode {
+    fn default() -> Self {
+        ScaleMode::Linear
+    }
+}
+
+/// How the values of `t` at which a mirror is sampled are chosen.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MirrorSampling {
+    /// `t` is sampled at regular steps across the interval. This is the default.
+    Uniform,
+    /// `t` is sampled more densely where the angle of the tangent to the mirror changes fastest,
+    /// which is more accurate for mirrors with sharp bends, for the same number of samples.
+    Angle,
+    /// `t` is sampled so that the samples are roughly equidistant along the mirror, which avoids
+    /// clustering samples where the mirror moves slowly with respect to `t`.
+    ArcLength,
+}
+
+impl Default for MirrorSampling {
+    fn default() -> Self {
+        MirrorSampling::Uniform
+    }
+}
+
+impl MirrorSampling {
+    /// The values of `t` at which to sample the `mirror` across the `interval`, in ascending order.
+    /// If the interval is periodic, `end` is not sampled, as it coincides with `start`.
+    pub fn sample<S: Scalar>(
+        self,
+        mirror: &Equation<'_, f64, Pair<S>>,
+        interval: &Interval,
+    ) -> Vec<f64> {
+        let uniform = Interval { periodic: false, ..interval.clone() };
+        let mut ts = match self {
+            MirrorSampling::Angle if uniform.iter().nth(1).is_some() => {
+                let samples = uniform.iter().count() as u64;
+                mirror.sample_tangent_angle(interval.start..=interval.end, samples)
+            }
+            MirrorSampling::ArcLength => {
+                let samples = uniform.iter().count();
+                mirror.sample_arc_uniform_parameters(interval, samples)
+            }
+            _ => uniform.iter().collect(),
+        };
+        if interval.periodic && ts.len() > 1 && ts.last() == Some(&interval.end) {
+            ts.pop();
+        }
+        ts
+    }
+}
+
+/// A symmetric logarithm: linear in the range [-1, 1] and logarithmic outside it, so that zero and
+/// negative values are handled gracefully. Powers of ten are mapped to evenly-spaced values:
+/// `±10^n ↦ ±(n + 1)` for `n ≥ 0`.
+pub fn signed_log(x: f64) -> f64 {
+    if x.abs() <= 1.0 {
+        x
+    } else {
+        x.signum() * (1.0 + x.abs().log10())
+    }
+}
+
+/// The inverse of `signed_log`.
+pub fn signed_exp(y: f64) -> f64 {
+    if y.abs() <= 1.0 {
+        y
+    } else {
+        y.signum() * 10.0f64.powf(y.abs() - 1.0)
+    }
+}
+
+impl fmt::Display for View {
+    /// A view is displayed as the size of the canvas, followed by the region it displays.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [width, height] = self.size().into_inner();
+        write!(
+            f,
+            "{}×{} px showing {}×{} around ({}, {}) (scale 2^{})",
+            self.width,
+            self.height,
+            width,
+            height,
+            self.origin.x(),
+            self.origin.y(),
+            self.scale,
+        )?;
+        if self.scale_mode == ScaleMode::Logarithmic {
+            write!(f, " on a logarithmic scale")?;
+        }
+        Ok(())
+    }
 }
 
 impl View {
+    /// Construct a view of the given dimensions (in pixels) framing all the (finite) `points`,
+    /// leaving a `margin` (in pixels) between the points and the edges of the canvas. If the points
+    /// have no extent (e.g. there is a single point), the view is centred on them at a scale of 0.
+    /// Every point projects inside the canvas, even with no margin: the extreme points lie half a
+    /// pixel within the edges, as the far edges of the canvas are excluded by `project`.
+    pub fn fit(points: &[Point2D], width: u16, height: u16, margin: f64) -> View {
+        let points = points.iter().filter(|point| point.is_finite());
+        let (min, max) = points.fold(
+            (Point2D::diag(f64::INFINITY), Point2D::diag(f64::NEG_INFINITY)),
+            |(min, max), &point| (min.min(point), max.max(point)),
+        );
+        // With no points, there is nothing to frame, so we centre the view on the origin.
+        let (min, max) = if min.x() <= max.x() {
+            (min, max)
+        } else {
+            (Point2D::zero(), Point2D::zero())
+        };
+
+        // The number of pixels available to display the points along each axis, between the
+        // centres of the outermost pixels inside the margin.
+        let available = Point2D::new([width as f64, height as f64])
+            .map(|length| (length - 2.0 * margin - 1.0).max(1.0));
+        // The cartesian distance per pixel needed to fit the points along the tighter axis.
+        let factor = ((max - min) / available).into_inner().iter().cloned().fold(0.0, f64::max);
+
+        View {
+            width,
+            height,
+            origin: (min + max) / Point2D::diag(2.0),
+            scale: if factor > 0.0 { factor.log2() } else { 0.0 },
+            scale_mode: ScaleMode::Linear,
+        }
+    }
+
     /// Returns the width and height of the region in cartesian distances.
     pub fn size(&self) -> Point2D {
         let factor = 2.0f64.powf(self.scale);
@@ -141,12 +855,16 @@ impl View {
 
     /// Takes a point in cartesian coördinates and returns the corresponding pixel coördinates of
     /// the point in the given region.
-    pub fn project(&self, p: Point2D, region: [usize; 2]) -> Option<[usize; 2]> {
-        if p.is_nan() {
+    pub fn project<S: Scalar>(&self, p: Pair<S>, region: [usize; 2]) -> Option<[usize; 2]> {
+        // Points at infinity (e.g. at a singularity of `tan`) lie outside every view, but would
+        // otherwise be transformed into NaN or compared unreliably with the bounds of the view.
+        if !p.is_finite() {
             return None;
         }
+        let p = p.map(S::as_f64);
 
-        let q = p - (self.origin - self.size() / Point2D::diag(2.0));
+        let corner = self.transform(self.origin) - self.size() / Point2D::diag(2.0);
+        let q = self.transform(p) - corner;
         if q >= Point2D::zero() && q < self.size() {
             let region = Point2D::new([region[0] as f64, region[1] as f64]);
             let [x, y] = (q * region / self.size()).into_inner();
@@ -155,4 +873,32 @@ impl View {
             None
         }
     }
+
+    /// Whether the point lies within the region displayed by the view.
+    pub fn contains<S: Scalar>(&self, p: Pair<S>) -> bool {
+        self.project(p, [1, 1]).is_some()
+    }
+
+    /// Takes the pixel coördinates of a cell in the given region and returns the cartesian
+    /// coördinates of the centre of the cell. This is the inverse of `project`, in the sense that
+    /// projecting the resulting point gives back the same cell.
+    pub fn unproject(&self, pixel: [usize; 2], region: [usize; 2]) -> Point2D {
+        let pixel = Point2D::new([pixel[0] as f64, pixel[1] as f64]) + Point2D::diag(0.5);
+        let region = Point2D::new([region[0] as f64, region[1] as f64]);
+        let q = pixel * self.size() / region;
+        let corner = self.transform(self.origin) - self.size() / Point2D::diag(2.0);
+        let p = q + corner;
+        match self.scale_mode {
+            ScaleMode::Linear => p,
+            ScaleMode::Logarithmic => p.map(signed_exp),
+        }
+    }
+
+    /// Transform cartesian coördinates according to the scale mode.
+    fn transform(&self, p: Point2D) -> Point2D {
+        match self.scale_mode {
+            ScaleMode::Linear => p,
+            ScaleMode::Logarithmic => p.map(signed_log),
+        }
+    }
 }
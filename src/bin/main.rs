@@ -0,0 +1,29 @@
+use reflections::approximation::{Interval, ScaleMode, View};
+use reflections::{proof_of_concept, ProofOfConceptArgs};
+use reflections::spatial::Point2D;
+
+/// Print the reflection of a line in a parabolic mirror, using the rendering method given as the
+/// first argument (by default, `quadratic`).
+fn main() {
+    let method = std::env::args().nth(1).unwrap_or_else(|| "quadratic".to_string());
+    let view = View {
+        width: 640,
+        height: 480,
+        origin: Point2D::zero(),
+        scale: 0.0,
+        scale_mode: ScaleMode::Linear,
+    };
+
+    if let Err(error) = proof_of_concept(ProofOfConceptArgs {
+        offsets: (0.0, 0.0),
+        mirror: ["t", "(t / 10) ^ 2"],
+        figure: ["t", "0"],
+        sigma_tau: ["-s", "t"],
+        method: &method,
+        threshold: 4.0,
+        interval: Interval::new(-256.0, 256.0, 1.0),
+        view,
+    }) {
+        eprintln!("failed to render the reflection: {:?}", error);
+    }
+}
@@ -236,6 +236,8 @@ class View {
         [this.width, this.height] = [canvas.width, canvas.height];
         // The zoom factor, on a base-2 exponential scale. I.e. 0 is unzoomed; 1 is 2x; -1 is 0.5x.
         this.scale = 0;
+        // How coördinates are mapped onto the canvas: either `"linear"` or `"logarithmic"`.
+        this.scale_mode = "linear";
     }
 }
 
@@ -323,11 +325,14 @@ const SPECIAL_VARIABLES = new Map([
 
 /// The class `Binding` mirrors the Rust struct `Binding` and should be kept in sync.
 class Binding {
-    constructor(value, min, max, step) {
+    constructor(value, min, max, step, periodic = false) {
         this.value = value;
         this.min = min;
         this.max = max;
+        // The Rust `Binding` also accepts a resolution, `samples_per_unit`, in place of `step`.
         this.step = step;
+        // Whether `max` is identified with `min`, e.g. for `t` parameterising a closed curve.
+        this.periodic = periodic;
     }
 }
 
@@ -344,10 +349,24 @@ class NonaffineReflection {
                 this.view = view;
                 this.mirror = mirror;
                 this.figure = figure;
+                // `sigma_tau` may be `null` (or blank) for the identity, `(s, t)`.
                 this.sigma_tau = sigma_tau;
                 this.bindings = bindings;
                 this.method = method;
+                // The tolerance of the approximation in pixels, whichever the method.
                 this.threshold = threshold;
+                // Reflection points closer together than `tolerance` are merged, unless it is
+                // `null`.
+                this.tolerance = null;
+                // Either `"uniform"`, `"angle"` to sample the mirror more densely where its
+                // tangent turns fastest, or `"arclength"` to space the samples evenly along it.
+                this.mirror_sampling = "uniform";
+                // Whether to omit reflection points whose images lie outside the view.
+                this.clip_to_view = false;
+                // The maximum number of points to sample, or `null` for the default.
+                this.max_points = null;
+                // Whether to return the normals to the mirror, to be drawn under the reflection.
+                this.normals = false;
             }
         }
 
@@ -361,6 +380,10 @@ class NonaffineReflection {
                 // whereas `reflection` extracts solely the image points for convenience.
                 this.points = data.reflection;
                 this.reflection = data.reflection.map(([r,,]) => r);
+                // Each warning mirrors the Rust enum `RenderWarning` and should be kept in sync.
+                this.warnings = data.warnings;
+                // Pairs of endpoints of the normals to the mirror, if they were requested.
+                this.normals = data.normals;
             }
         }
 
@@ -381,13 +404,25 @@ class NonaffineReflection {
                 ),
             ));
             PerformanceLogger.mark(this.log_index, PERFORMANCE_MARKERS.WASM_BINDGEN_CALL);
+            let response;
             try {
-                const data = new RenderReflectionData(JSON.parse(json));
-                PerformanceLogger.mark(this.log_index, PERFORMANCE_MARKERS.WASM_BINDGEN_PARSE);
-                resolve(data);
+                response = JSON.parse(json);
             } catch (err) {
                 reject(new Error("Failed to parse Rust data."));
+                return;
             }
+            // The `error` field mirrors the Rust enum `RenderError` and should be kept in sync.
+            if (!response.ok) {
+                const { kind, detail } = response.error;
+                reject(new Error(`Failed to render reflection (${kind}): ${detail}`));
+                return;
+            }
+            const data = new RenderReflectionData(response.data);
+            for (const { kind } of data.warnings) {
+                console.warn(`Rendered reflection with warning: ${kind}.`);
+            }
+            PerformanceLogger.mark(this.log_index, PERFORMANCE_MARKERS.WASM_BINDGEN_PARSE);
+            resolve(data);
         });
     }
 
@@ -398,6 +433,10 @@ class NonaffineReflection {
         }
 
         const data = await this.data;
+        canvas.context.fillStyle = canvas.context.strokeStyle = get_CSS_var("--normal-colour");
+        for (const normal of data.normals) {
+            canvas.plot_equation(view, normal);
+        }
         canvas.context.fillStyle = canvas.context.strokeStyle = get_CSS_var("--figure-colour");
         canvas.plot_equation(view, data.figure);
         canvas.context.fillStyle = canvas.context.strokeStyle = get_CSS_var("--mirror-colour");
@@ -417,7 +456,11 @@ class Equation {
     }
 
     variables() {
-        return new Set(this.string.match(/\b[a-z]\b/g));
+        // Variables are single Latin or Greek letters, excluding the constants `π` and `τ`. This
+        // should be kept in sync with `Parser::parse_var` in Rust.
+        const LETTER = "A-Za-zα-ωΑ-Ω";
+        const variable = new RegExp(`(?<![${LETTER}])[${LETTER}](?![${LETTER}])`, "g");
+        return new Set((this.string.match(variable) || []).filter(v => v !== "π" && v !== "τ"));
     }
 
     toString() {
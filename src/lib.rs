@@ -12,19 +12,23 @@ use console_error_panic_hook;
 pub mod approximation;
 pub mod parser;
 pub mod reflectors;
-// We don't actually make use of `sampling` yet, but we'd like to make sure it continues to compile.
 pub mod sampling;
 pub mod spatial;
 
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::sync::Arc;
 
 use wasm_bindgen::prelude::wasm_bindgen;
 
-use crate::approximation::Equation;
-use crate::approximation::{Interval, View};
-use crate::parser::{Lexer, Parser};
+use crate::approximation::{Equation, MaybeSendSync, DEFAULT_H};
+use crate::approximation::{Interval, MirrorSampling, View};
+use crate::parser::ParseError;
 use crate::reflectors::{RasterisationApproximator, LinearApproximator, QuadraticApproximator};
-use crate::reflectors::ReflectionApproximator;
+use crate::reflectors::{deduplicate, normal_segments, MAX_GRID_CELLS};
+use crate::reflectors::{FlatMirrorApproximator, ReflectionApproximator, ReflectionInputs};
 use crate::spatial::Point2D;
 
 // It's helpful to be able to log error messages to the JavaScript console, so we export some
@@ -41,45 +45,332 @@ macro_rules! console_log {
     ($($t:tt)*) => (console_log(&format_args!($($t)*).to_string()))
 }
 
-/// Construct a parametric equation given the strings corresponding to `x(t)` and `y(t)`.
+/// The ways in which rendering a reflection can fail.
+///
+/// The enum `RenderError` is serialised as `{ kind, detail }`, which the JavaScript client relies
+/// upon, so the two should be kept in sync.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", content = "detail", rename_all = "snake_case")]
+pub enum RenderError {
+    /// The arguments could not be deserialised.
+    InvalidArguments(String),
+    /// An equation could not be lexed or parsed.
+    InvalidEquation(String),
+    /// An equation references variables that have not been bound.
+    UnboundVariables(String),
+    /// The interval for the named parameter could not be sampled.
+    InvalidInterval(String),
+    /// The rendering method was not recognised.
+    UnknownMethod(String),
+    /// Sampling the equations would produce more points than permitted by `max_points`.
+    TooManyPoints(String),
+    /// The threshold is outside the range accepted by the rendering method.
+    InvalidThreshold(String),
+}
+
+/// Conditions under which a reflection was rendered successfully, but which the user may wish to be
+/// made aware of.
+///
+/// The enum `RenderWarning` is serialised as `{ kind, detail }`, like `RenderError`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "detail", rename_all = "snake_case")]
+pub enum RenderWarning {
+    /// No point of the figure has a reflection (within the view, if clipping to the view), e.g.
+    /// because the figure lies outside the region covered by the normals to the mirror.
+    NoReflection,
+}
+
+/// The maximum number of points that `render_reflection` samples, unless otherwise specified. This
+/// prevents a fine step from exhausting the memory available (particularly in the browser).
+pub const DEFAULT_MAX_POINTS: u64 = 10_000_000;
+
+/// The maximum number of parsed expressions to cache before the cache is cleared.
+const PARSE_CACHE_CAPACITY: usize = 64;
+
+/// Statistics about the cache of parsed expressions, for the current thread.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ParseCacheStats {
+    /// The number of times an expression was found in the cache.
+    pub hits: usize,
+    /// The number of times an expression had to be lexed and parsed.
+    pub misses: usize,
+}
+
+thread_local! {
+    /// Expressions that have already been parsed, keyed by their source strings. The client
+    /// re-renders whenever a binding changes, though the equations themselves rarely do, so this
+    /// saves lexing and parsing them each time. WASM is single-threaded, so a thread-local cache
+    /// suffices.
+    static PARSE_CACHE: RefCell<(HashMap<String, Arc<parser::Expr>>, ParseCacheStats)> =
+        RefCell::new((HashMap::new(), ParseCacheStats::default()));
+}
+
+/// The statistics of the cache of parsed expressions used by `render_reflection`.
+pub fn parse_cache_stats() -> ParseCacheStats {
+    PARSE_CACHE.with(|cache| cache.borrow().1)
+}
+
+/// Parse the strings corresponding to `x(t)` and `y(t)` of a parametric equation. Every variable
+/// in the equation must either be one of the `parameters` or be bound in `static_bindings`.
+fn parse_equations(
+    string: [&str; 2],
+    parameters: &[char],
+    static_bindings: &HashMap<char, f64>,
+) -> Result<[Arc<parser::Expr>; 2], RenderError> {
+    /// Convert a string into an expression, which can then be evaluated to create an equation.
+    /// Expressions are cached, so that each string is parsed only once.
+    fn parse_equation(string: &str) -> Result<Arc<parser::Expr>, RenderError> {
+        PARSE_CACHE.with(|cache| {
+            let (exprs, stats) = &mut *cache.borrow_mut();
+            if let Some(expr) = exprs.get(string) {
+                stats.hits += 1;
+                return Ok(Arc::clone(expr));
+            }
+            stats.misses += 1;
+
+            let expr = Arc::new(string.parse::<parser::Expr>()?);
+            if exprs.len() >= PARSE_CACHE_CAPACITY {
+                exprs.clear();
+            }
+            exprs.insert(string.to_string(), Arc::clone(&expr));
+            Ok(expr)
+        })
+    }
+
+    let expr = [parse_equation(string[0])?, parse_equation(string[1])?];
+    parser::check_bound([&expr[0], &expr[1]], string, parameters, static_bindings)?;
+    Ok(expr)
+}
+
+impl From<ParseError> for RenderError {
+    fn from(error: ParseError) -> Self {
+        match error {
+            ParseError::InvalidEquation(message) |
+            ParseError::TooDeeplyNested(message) |
+            ParseError::TooLong(message) => RenderError::InvalidEquation(message),
+            ParseError::UnboundVariables(message) => RenderError::UnboundVariables(message),
+        }
+    }
+}
+
+/// Construct a parametric equation given the strings corresponding to `x(t)` and `y(t)`. Every
+/// variable in the equation must either be one of the `parameters` (which are bound by
+/// `set_bindings`) or be bound in `static_bindings`.
 fn construct_equation<'a, I>(
     string: [&str; 2],
+    parameters: &[char],
     static_bindings: &'a HashMap<char, f64>,
-    set_bindings: impl 'a + Fn(&mut HashMap<char, f64>, I),
-) -> Result<Equation<'a, I>, ()> {
-    /// Convert a string into an expression, which can then be evaluated to create an equation.
-    fn parse_equation(string: &str) -> Result<parser::Expr, ()> {
-        if let Ok(lexemes) = Lexer::scan(string.chars()) {
-            let tokens = Lexer::evaluate(lexemes.into_iter()).collect();
-            let mut parser = Parser::new(tokens);
-            parser.parse()
-        } else {
-            Err(())
+    set_bindings: impl 'a + Fn(&mut HashMap<char, f64>, I) + MaybeSendSync,
+) -> Result<Equation<'a, I>, RenderError> {
+    let expr = parse_equations(string, parameters, static_bindings)?;
+    Ok(Equation::new(box move |p| {
+        let mut bindings = HashMap::new();
+        set_bindings(&mut bindings, p);
+        Point2D::new([
+            expr[0].evaluate((&bindings, static_bindings)),
+            expr[1].evaluate((&bindings, static_bindings)),
+        ])
+    }))
+}
+
+/// Construct a parametric equation in the single parameter `t`, like `construct_equation`. Such
+/// an equation may also be sampled in batches, which avoids allocating bindings for every point.
+fn construct_curve<'a>(
+    string: [&str; 2],
+    static_bindings: &'a HashMap<char, f64>,
+) -> Result<Equation<'a, f64>, RenderError> {
+    let expr = parse_equations(string, &['t'], static_bindings)?;
+    Ok(Equation::from_exprs(expr, static_bindings))
+}
+
+/// The source strings of a parametric equation, `x(t)` and `y(t)`. Unlike an `Equation`, this may
+/// be serialised (e.g. to save a session), and the equation reconstructed using `from_spec`.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct EquationSpec {
+    pub x: String,
+    pub y: String,
+}
+
+/// Construct a parametric equation from its specification. See `construct_equation`.
+pub fn from_spec<'a, I>(
+    spec: &EquationSpec,
+    parameters: &[char],
+    static_bindings: &'a HashMap<char, f64>,
+    set_bindings: impl 'a + Fn(&mut HashMap<char, f64>, I) + MaybeSendSync,
+) -> Result<Equation<'a, I>, RenderError> {
+    construct_equation([&spec.x, &spec.y], parameters, static_bindings, set_bindings)
+}
+
+/// The names of the methods accepted by `render_reflection`, which are the names of the
+/// corresponding approximators.
+pub fn available_methods() -> &'static [&'static str] {
+    &["rasterisation", "linear", "quadratic", "flat"]
+}
+
+/// The names of the methods accepted by `render_reflection`, as a JSON array, so that the
+/// JavaScript client need not duplicate them.
+#[wasm_bindgen]
+pub extern fn render_methods() -> String {
+    json!(available_methods()).to_string()
+}
+
+/// The methods by which a reflection may be approximated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Method {
+    Rasterisation,
+    Linear,
+    Quadratic,
+    /// Exact reflection in a flat mirror, falling back to `Linear` for other mirrors.
+    Flat,
+}
+
+impl FromStr for Method {
+    type Err = RenderError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "rasterisation" => Method::Rasterisation,
+            "linear" => Method::Linear,
+            "quadratic" => Method::Quadratic,
+            "flat" => Method::Flat,
+            _ => return Err(RenderError::UnknownMethod(s.to_string())),
+        })
+    }
+}
+
+impl Method {
+    /// Construct the approximator corresponding to the method. The interpretation of `threshold`
+    /// depends on the method. For rasterisation, the threshold is rounded to the nearest whole
+    /// number of pixels (of at least 1), and should be checked with `check_threshold` first.
+    pub fn approximator(
+        self,
+        threshold: f64,
+        mirror_sampling: MirrorSampling,
+    ) -> Box<dyn ReflectionApproximator> {
+        match self {
+            Method::Rasterisation => box RasterisationApproximator {
+                cell_size: (threshold.round() as u16).max(1),
+                mirror_sampling,
+            },
+            Method::Linear => box LinearApproximator {
+                threshold,
+                mirror_sampling,
+                intersect_straight_figures: false,
+            },
+            Method::Quadratic => box QuadraticApproximator { mirror_sampling },
+            Method::Flat => box FlatMirrorApproximator {
+                fallback: LinearApproximator {
+                    threshold,
+                    mirror_sampling,
+                    intersect_straight_figures: false,
+                },
+            },
         }
     }
 
-    let expr = [parse_equation(string[0])?, parse_equation(string[1])?];
-    Ok(Equation {
-        function: box move |p| {
-            let mut bindings = HashMap::new();
-            set_bindings(&mut bindings, p);
-            Point2D::new([
-                expr[0].evaluate((&bindings, static_bindings)),
-                expr[1].evaluate((&bindings, static_bindings)),
-            ])
-        },
-    })
+    /// Check that a threshold in pixels is accepted by the method for the `view`. Every threshold
+    /// must be finite and nonnegative; thresholds for rasterisation, which are the widths of the
+    /// cells, must additionally be at most `u16::MAX`, rather than being silently truncated, and
+    /// large enough that the view is divided into at most `MAX_GRID_CELLS` cells.
+    pub fn check_threshold(self, pixels: f64, view: &View) -> Result<(), RenderError> {
+        if !(pixels >= 0.0 && pixels.is_finite()) {
+            return Err(RenderError::InvalidThreshold(format!(
+                "{} is not a finite, nonnegative number of pixels",
+                pixels,
+            )));
+        }
+        if self == Method::Rasterisation && pixels.round() > u16::MAX as f64 {
+            return Err(RenderError::InvalidThreshold(format!(
+                "{} exceeds the largest cell size for rasterisation, {}",
+                pixels,
+                u16::MAX,
+            )));
+        }
+        let cell_size = (pixels.round() as u16).max(1);
+        if self == Method::Rasterisation
+            && RasterisationApproximator::grid_size(cell_size, view).is_none()
+        {
+            return Err(RenderError::InvalidThreshold(format!(
+                "cells of {} pixels would divide a {}×{} pixel view into more than {} cells",
+                cell_size,
+                view.width,
+                view.height,
+                MAX_GRID_CELLS,
+            )));
+        }
+        Ok(())
+    }
+
+    /// Convert a threshold in pixels of the `view` to the threshold expected by `approximator`, so
+    /// that a given threshold corresponds to a comparable tolerance for each method: the size of
+    /// the cells for rasterisation, and the (squared) distance from the normals for the linear
+    /// approximation. The quadratic approximation has no threshold.
+    pub fn threshold_from_pixels(self, pixels: f64, view: &View) -> f64 {
+        match self {
+            Method::Rasterisation | Method::Quadratic => pixels,
+            Method::Linear | Method::Flat => {
+                let distance = pixels * view.size().x() / view.width as f64;
+                distance * distance
+            }
+        }
+    }
 }
 
 /// A variable binding: a name and value, along with the range of values the variable can take.
 ///
 /// The struct `Binding` mirrors the JavaScript class `Binding` and should be kept in sync.
 #[derive(Clone, Debug, Deserialize)]
-struct Binding {
-    value: f64,
-    min: f64,
-    max: f64,
-    step: f64,
+pub struct Binding {
+    pub value: f64,
+    pub min: f64,
+    pub max: f64,
+    /// The spacing of the samples, given by exactly one of a `step` or a `samples_per_unit` field.
+    #[serde(flatten)]
+    pub spacing: Spacing,
+    /// Whether `max` is identified with `min`, e.g. for `t` parameterising a closed curve.
+    #[serde(default)]
+    pub periodic: bool,
+}
+
+/// The spacing of the samples of a variable binding.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(try_from = "SpacingFields")]
+pub enum Spacing {
+    /// The difference between consecutive samples.
+    Step(f64),
+    /// The resolution of the samples: the number of samples per unit of the variable.
+    SamplesPerUnit(f64),
+}
+
+/// The fields of a `Binding` from which its `Spacing` is deserialised, exactly one of which must be
+/// present.
+#[derive(Deserialize)]
+struct SpacingFields {
+    step: Option<f64>,
+    samples_per_unit: Option<f64>,
+}
+
+impl TryFrom<SpacingFields> for Spacing {
+    type Error = &'static str;
+
+    fn try_from(fields: SpacingFields) -> Result<Self, Self::Error> {
+        match (fields.step, fields.samples_per_unit) {
+            (Some(step), None) => Ok(Spacing::Step(step)),
+            (None, Some(samples)) => Ok(Spacing::SamplesPerUnit(samples)),
+            (Some(_), Some(_)) => Err("only one of `step` and `samples_per_unit` may be given"),
+            (None, None) => Err("one of `step` and `samples_per_unit` must be given"),
+        }
+    }
+}
+
+impl Spacing {
+    /// The difference between consecutive samples.
+    pub fn step(self) -> f64 {
+        match self {
+            Spacing::Step(step) => step,
+            Spacing::SamplesPerUnit(samples) => 1.0 / samples,
+        }
+    }
 }
 
 /// Set up the Rust WASM environment. Responsible primarily for setting up the error handlers.
@@ -88,113 +379,347 @@ pub extern fn initialise() {
     console_error_panic_hook::set_once();
 }
 
+/// The struct `RenderReflectionArgs` mirrors the JavaScript class `RenderReflectionArgs` and
+/// should be kept in sync. It may also be constructed directly, to be passed to `approximate`.
+#[derive(Deserialize)]
+pub struct RenderReflectionArgs<'a> {
+    pub view: View,
+    pub mirror: [&'a str; 2],
+    pub figure: [&'a str; 2],
+    /// If absent, or if both components are blank, `sigma_tau` is the identity, `(s, t)`.
+    #[serde(default)]
+    pub sigma_tau: Option<[&'a str; 2]>,
+    pub bindings: HashMap<&'a str, Binding>,
+    /// The name of the method of approximation. This is ignored by `render_all_methods`.
+    #[serde(default)]
+    pub method: &'a str,
+    /// The tolerance of the approximation, in pixels. This must be finite and nonnegative and,
+    /// for rasterisation, at most `u16::MAX` (it is rounded to a whole number of pixels), and
+    /// large enough that the grid has at most `MAX_GRID_CELLS` cells.
+    pub threshold: f64,
+    /// If present, reflection points closer together than the tolerance are merged.
+    #[serde(default)]
+    pub tolerance: Option<f64>,
+    /// How the values of `t` at which the mirror is sampled are chosen.
+    #[serde(default)]
+    pub mirror_sampling: MirrorSampling,
+    /// If set, reflection points whose images lie outside the view are omitted.
+    #[serde(default)]
+    pub clip_to_view: bool,
+    /// The maximum number of points to sample, or `DEFAULT_MAX_POINTS` if absent.
+    #[serde(default)]
+    pub max_points: Option<u64>,
+    /// If set, the segments of the normals to the mirror are returned along with the reflection.
+    #[serde(default)]
+    pub normals: bool,
+}
+
+/// The struct `RenderReflectionData` mirrors the JavaScript class `RenderReflectionData` and
+/// should be kept in sync. It is the `data` returned by a successful call to `render_reflection`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct RenderReflectionData {
+    pub mirror: Vec<Point2D>,
+    pub figure: Vec<Point2D>,
+    /// (reflection, figure, mirror) triples, as returned by the approximators.
+    pub reflection: Vec<(Point2D, Point2D, Point2D)>,
+    pub warnings: Vec<RenderWarning>,
+    /// The endpoints of the normal to the mirror at each sampled `t`, if requested with `normals`,
+    /// and empty otherwise.
+    #[serde(default)]
+    pub normals: Vec<(Point2D, Point2D)>,
+}
+
+/// Parse the JSON arguments to `render_reflection`.
+fn parse_args(json: &str) -> Result<RenderReflectionArgs<'_>, RenderError> {
+    serde_json::from_str(json).map_err(|err| RenderError::InvalidArguments(err.to_string()))
+}
+
+/// Compute the reflection described by `data` using each of the `methods`, or the method given by
+/// `data` if `methods` is `None`. The equations are parsed and sampled only once, however many
+/// methods there are.
+fn approximate_each(
+    data: &RenderReflectionArgs<'_>,
+    methods: Option<&[&str]>,
+) -> Result<Vec<RenderReflectionData>, RenderError> {
+    let binding = |name| {
+        data.bindings.get(name).ok_or_else(|| {
+            RenderError::InvalidArguments(format!("missing binding for `{}`", name))
+        })
+    };
+
+    // `t` and `s` are inherently special-cased. We use their values as offset parameters.
+    let (s_offset, t_offset) = (binding("s")?.value, binding("t")?.value);
+    let bindings: HashMap<char, f64> = data.bindings.iter().filter_map(|(name, binding)| {
+        match (name.chars().count(), name) {
+            (_, &"s") | (_, &"t") => None,
+            (1, _) => name.chars().next().map(|c| (c, binding.value)),
+            _ => None,
+        }
+    }).collect();
+
+    let figure = construct_curve(data.figure, &bindings)?;
+    let mirror = construct_curve(data.mirror, &bindings)?;
+    let sigma_tau = match data.sigma_tau {
+        Some(sigma_tau) if sigma_tau.iter().any(|eq| !eq.trim().is_empty()) => {
+            construct_equation(sigma_tau, &['s', 't'], &bindings, |bindings, (s, t)| {
+                bindings.insert('s', s - s_offset);
+                bindings.insert('t', t - t_offset);
+            })?
+        }
+        // The identity is equivalent to `["s", "t"]`, without the cost of evaluating expressions.
+        _ => Equation::new(box move |(s, t)| Point2D::new([s - s_offset, t - t_offset])),
+    };
+
+    // The intervals over which to sample `t` and `s`.
+    let interval = |name| {
+        let binding = binding(name)?;
+        let step = binding.spacing.step();
+        // A non-positive step cannot be used to sample an interval, nor can an infinite one, which
+        // arises from a resolution of zero.
+        if step > 0.0 && step.is_finite() {
+            let interval = Interval::new(binding.min, binding.max, step);
+            Ok(Interval { periodic: binding.periodic, ..interval })
+        } else {
+            Err(RenderError::InvalidInterval(name.to_string()))
+        }
+    };
+    let (t_interval, s_interval) = (interval("t")?, interval("s")?);
+
+    // The figure is sampled along `t`, and the normals to the mirror along `s` at each `t`. We
+    // check this before sampling anything, as the samples may not even fit in memory.
+    let max_points = data.max_points.unwrap_or(DEFAULT_MAX_POINTS);
+    let (t_samples, s_samples) = (t_interval.sample_count(), s_interval.sample_count());
+    let points = t_samples.saturating_mul(s_samples).saturating_add(t_samples);
+    if points > max_points {
+        return Err(RenderError::TooManyPoints(format!(
+            "sampling would produce {} points, but at most {} are permitted",
+            points,
+            max_points,
+        )));
+    }
+    if let Some(tolerance) = data.tolerance {
+        if tolerance <= 0.0 || tolerance.is_nan() {
+            return Err(RenderError::InvalidArguments("non-positive tolerance".to_string()));
+        }
+    }
+    // Features of the mirror finer than the step of `t` aren't resolved when sampling it, so
+    // neither should they be when approximating its normals.
+    let mirror = mirror.with_h(t_interval.step.min(DEFAULT_H));
+
+    let figure = figure.sample(&t_interval);
+    let mirror_points = mirror.sample(&t_interval);
+    let normals = if data.normals {
+        normal_segments(&mirror, &t_interval, &s_interval, data.mirror_sampling)
+    } else {
+        vec![]
+    };
+
+    let methods = match methods {
+        Some(methods) => methods.to_vec(),
+        None => vec![data.method],
+    };
+    methods.into_iter().map(|method| {
+        let method: Method = method.parse()?;
+        method.check_threshold(data.threshold, &data.view)?;
+        let threshold = method.threshold_from_pixels(data.threshold, &data.view);
+        let approximator = method.approximator(threshold, data.mirror_sampling);
+        let inputs = ReflectionInputs {
+            mirror: &mirror,
+            figure: &figure,
+            sigma_tau: &sigma_tau,
+            t_interval: &t_interval,
+            s_interval: &s_interval,
+        };
+        let mut reflection = approximator.approximate_reflection(inputs, &data.view, None);
+        if let Some(tolerance) = data.tolerance {
+            reflection = deduplicate(reflection, tolerance);
+        }
+        if data.clip_to_view {
+            reflection.retain(|&(image, _, _)| data.view.contains(image));
+        }
+
+        let mut warnings = vec![];
+        if reflection.is_empty() {
+            warnings.push(RenderWarning::NoReflection);
+        }
+
+        Ok(RenderReflectionData {
+            mirror: mirror_points.clone(),
+            figure: figure.clone(),
+            reflection,
+            warnings,
+            normals: normals.clone(),
+        })
+    }).collect()
+}
+
+/// Approximate a generalised reflection given a mirror and figure. This is the typed equivalent of
+/// `render_reflection`, for use from Rust.
+pub fn approximate(args: RenderReflectionArgs<'_>) -> Result<RenderReflectionData, RenderError> {
+    approximate_each(&args, None).map(|mut data| data.remove(0))
+}
+
+/// Compute the reflection described by the JSON arguments to `render_reflection`.
+fn render(json: &str) -> Result<RenderReflectionData, RenderError> {
+    approximate(parse_args(json)?)
+}
+
 /// Approximate a generalised reflection given a mirror and figure, as a set of points.
 #[wasm_bindgen]
 pub extern fn render_reflection(
     json: String,
 ) -> String {
-    /// The struct `RenderReflectionArgs` mirrors the JavaScript class `RenderReflectionArgs` and
-    /// should be kept in sync.
-    #[derive(Deserialize)]
-    struct RenderReflectionArgs<'a> {
-        view: View,
-        mirror: [&'a str; 2],
-        figure: [&'a str; 2],
-        sigma_tau: [&'a str; 2],
-        bindings: HashMap<&'a str, Binding>,
-        method: &'a str,
-        threshold: f64,
-    }
+    // The JavaScript client distinguishes success and failure using the `ok` field.
+    match render(&json) {
+        Ok(data) => json!({ "ok": true, "data": data }),
+        Err(error) => json!({ "ok": false, "error": error }),
+    }.to_string()
+}
 
-    /// The struct `RenderReflectionData` mirrors the JavaScript class `RenderReflectionData` and
-    /// should be kept in sync.
-    #[derive(Serialize)]
-    struct RenderReflectionData {
-        mirror: Vec<Point2D>,
-        figure: Vec<Point2D>,
-        reflection: Vec<(Point2D, Point2D, Point2D)>,
-    }
+/// Approximate a generalised reflection using every method in `available_methods`, for comparison.
+/// This takes the same arguments as `render_reflection` (except that `method` is ignored), and the
+/// `data` in the response maps the name of each method to the data it would have produced.
+#[wasm_bindgen]
+pub extern fn render_all_methods(
+    json: String,
+) -> String {
+    match parse_args(&json).and_then(|args| approximate_each(&args, Some(available_methods()))) {
+        Ok(data) => {
+            let data: serde_json::Map<_, _> = available_methods().iter()
+                .map(|method| method.to_string())
+                .zip(data.into_iter().map(|data| json!(data)))
+                .collect();
+            json!({ "ok": true, "data": data })
+        }
+        Err(error) => json!({ "ok": false, "error": error }),
+    }.to_string()
+}
 
-    // An empty string represents an error to the JavaScript client.
-    let error_output = String::new();
-
-    if let Ok(data) = serde_json::from_str::<RenderReflectionArgs>(&json) {
-        // `t` and `s` are inherently special-cased. We use their values as offset parameters.
-        let (s_offset, t_offset) = (data.bindings["s"].value, data.bindings["t"].value);
-        let bindings: HashMap<char, f64> = data.bindings.iter().filter_map(|(name, binding)| {
-            match (name.len(), name) {
-                (_, &"s") | (_, &"t") => None,
-                (1, _) => name.chars().next().map(|c| (c, binding.value)),
-                _ => None,
-            }
-        }).collect();
-
-        let (figure, mirror, sigma_tau) = match (
-            construct_equation(data.figure, &bindings, |bindings, t| {
-                bindings.insert('t', t);
-            }),
-            construct_equation(data.mirror, &bindings, |bindings, t| {
-                bindings.insert('t', t);
-            }),
-            construct_equation(data.sigma_tau, &bindings, |bindings, (s, t)| {
-                bindings.insert('s', s - s_offset);
-                bindings.insert('t', t - t_offset);
-            }),
-        ) {
-            (Ok(figure), Ok(mirror), Ok(sigma_tau)) => (figure, mirror, sigma_tau),
-            _ => return error_output,
-        };
+/// Approximate a generalised reflection given a mirror and figure, taking the same arguments as
+/// `render_reflection`, but returning an SVG document the size of the view, in which the mirror
+/// and figure are drawn as polylines and the reflection as a path of dots. On failure, the same
+/// JSON error as `render_reflection` is returned instead.
+#[wasm_bindgen]
+pub extern fn render_reflection_svg(
+    json: String,
+) -> String {
+    let args = match parse_args(&json) {
+        Ok(args) => args,
+        Err(error) => return json!({ "ok": false, "error": error }).to_string(),
+    };
+    let data = match approximate_each(&args, None) {
+        Ok(mut data) => data.remove(0),
+        Err(error) => return json!({ "ok": false, "error": error }).to_string(),
+    };
+    let view = &args.view;
 
-        // The interval over which to sample `t`.
-        // For now, we use the same interval for sampling `s`, to simplify the interface.
-        let interval = Interval {
-            start: data.bindings["t"].min,
-            end: data.bindings["t"].max,
-            step: data.bindings["t"].step,
-        };
+    // Project a point into SVG coördinates, which (unlike cartesian coördinates) increase
+    // downwards, so that the bottom row of pixels is at `height - 1`.
+    let region = [view.width as usize, view.height as usize];
+    let project = |p: Point2D| {
+        view.project(p, region).map(|[x, y]| format!("{},{}", x, view.height as usize - 1 - y))
+    };
 
-        let reflection = match data.method.as_ref() {
-            "rasterisation" => {
-                let approximator = RasterisationApproximator {
-                    cell_size: (data.threshold as u16).max(1),
-                };
-                approximator.approximate_reflection(
-                    &mirror,
-                    &figure,
-                    &sigma_tau,
-                    &interval,
-                    &data.view,
-                )
-            }
-            "linear" => {
-                let approximator = LinearApproximator { threshold: data.threshold };
-                approximator.approximate_reflection(
-                    &mirror,
-                    &figure,
-                    &sigma_tau,
-                    &interval,
-                    &data.view,
-                )
-            }
-            "quadratic" => {
-                let approximator = QuadraticApproximator;
-                approximator.approximate_reflection(
-                    &mirror,
-                    &figure,
-                    &sigma_tau,
-                    &interval,
-                    &data.view,
-                )
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+         viewBox=\"0 0 {0} {1}\">\n",
+        view.width,
+        view.height,
+    );
+
+    // These colours match those in `main.css`.
+    const MIRROR_COLOUR: &str = "hsl(0, 100%, 50%)";
+    const FIGURE_COLOUR: &str = "hsl(190, 100%, 50%)";
+    const REFLECTION_COLOUR: &str = "hsl(280, 100%, 50%)";
+
+    // Equations are drawn as polylines, which are broken wherever the equation leaves the view.
+    for (points, colour) in &[(&data.mirror, MIRROR_COLOUR), (&data.figure, FIGURE_COLOUR)] {
+        let mut run = vec![];
+        for &point in points.iter().chain(std::iter::once(&Point2D::diag(f64::NAN))) {
+            if let Some(p) = project(point) {
+                run.push(p);
+            } else if !run.is_empty() {
+                svg += &format!(
+                    "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" \
+                     stroke-width=\"2\"/>\n",
+                    run.join(" "),
+                    colour,
+                );
+                run.clear();
             }
-            _ => panic!("unknown rendering method"),
-        };
+        }
+    }
 
-        json!(RenderReflectionData {
-            mirror: mirror.sample(&interval),
-            figure: figure.sample(&interval),
-            reflection,
-        }).to_string()
-    } else {
-        error_output
+    // Each point in the reflection is drawn as a zero-length segment with a round cap.
+    let dots: Vec<_> = data.reflection.iter()
+        .filter_map(|&(image, _, _)| project(image))
+        .map(|p| format!("M{}h0", p))
+        .collect();
+    svg += &format!(
+        "<path d=\"{}\" stroke=\"{}\" stroke-width=\"2\" stroke-linecap=\"round\"/>\n",
+        dots.join(""),
+        REFLECTION_COLOUR,
+    );
+
+    svg += "</svg>\n";
+    svg
+}
+
+/// The arguments to `proof_of_concept`, which are a simplified form of `RenderReflectionArgs`.
+pub struct ProofOfConceptArgs<'a> {
+    /// The offsets applied to `s` and `t` in `sigma_tau`, as with the bindings passed to
+    /// `render_reflection`.
+    pub offsets: (f64, f64),
+    pub mirror: [&'a str; 2],
+    pub figure: [&'a str; 2],
+    pub sigma_tau: [&'a str; 2],
+    /// The name of the method of approximation.
+    pub method: &'a str,
+    /// As for `render_reflection`, the tolerance of the approximation, in pixels of the `view`.
+    pub threshold: f64,
+    /// The interval over which both `s` and `t` are sampled.
+    pub interval: Interval,
+    pub view: View,
+}
+
+/// Approximate a generalised reflection outside of the browser, printing the points of the
+/// reflection to standard output, one per line. This is useful for testing and benchmarking the
+/// approximators natively.
+pub fn proof_of_concept(args: ProofOfConceptArgs<'_>) -> Result<(), RenderError> {
+    let ProofOfConceptArgs {
+        offsets: (s_offset, t_offset),
+        mirror,
+        figure,
+        sigma_tau,
+        method,
+        threshold,
+        interval,
+        view,
+    } = args;
+    let bindings = HashMap::new();
+    let figure = construct_curve(figure, &bindings)?;
+    let mirror = construct_curve(mirror, &bindings)?.with_h(interval.step.min(DEFAULT_H));
+    let sigma_tau = construct_equation(sigma_tau, &['s', 't'], &bindings, |bindings, (s, t)| {
+        bindings.insert('s', s - s_offset);
+        bindings.insert('t', t - t_offset);
+    })?;
+
+    let method: Method = method.parse()?;
+    method.check_threshold(threshold, &view)?;
+    let threshold = method.threshold_from_pixels(threshold, &view);
+    let approximator = method.approximator(threshold, MirrorSampling::Uniform);
+    let inputs = ReflectionInputs {
+        mirror: &mirror,
+        figure: &figure.sample(&interval),
+        sigma_tau: &sigma_tau,
+        t_interval: &interval,
+        s_interval: &interval,
+    };
+    let reflection = approximator.approximate_reflection(inputs, &view, None);
+
+    for (image, _, _) in reflection {
+        println!("{} {}", image.x(), image.y());
     }
+
+    Ok(())
 }
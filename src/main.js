@@ -217,6 +217,11 @@ document.addEventListener("DOMContentLoaded", () => {
     RustWASMContext.connect(log_index).then(() => {
         canvas_offset = canvas.element.getBoundingClientRect();
 
+        for (const method of JSON.parse(window.wasm_bindgen.render_methods())) {
+            new Option(method, method[0].toUpperCase() + method.slice(1)).append_to(method_select);
+        }
+        method_select.value = settings.get("method");
+
         render(true, true);
     });
 
@@ -318,15 +323,13 @@ document.addEventListener("DOMContentLoaded", () => {
     }
 
     // Now we construct all the UI elements.
+    const method_select = new Select(new Map());
     const equation_container = new Div(["options"])
         .append_to(embedded ? new Div() : body);
 
     new Div(["dev-options"]).append_to(equation_container).append(
-        // Rendering method.
-        new Select(
-            new Map(["Rasterisation", "Linear", "Quadratic"].map(m => [m.toLowerCase(), m])),
-            settings.get("method"),
-        ).listen("input", (_, self) => {
+        // Rendering method. The methods are populated once we have connected to the WASM context.
+        method_select.listen("input", (_, self) => {
             settings.set("method", self.value);
             render(true);
         }),
@@ -1,10 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::f64;
 use std::fmt;
 use std::mem;
 use std::str::FromStr;
 use std::vec::IntoIter;
 
+use num_complex::Complex;
+
 /// String matching varieties: prefix or exact match.
 #[derive(PartialEq)]
 enum MatchKind {
@@ -20,11 +22,19 @@ pub enum Token {
     Name(String),
     OpenParen,
     CloseParen,
+    Comma,
+    Bar,
     Add,
     Sub,
     Mul,
     Div,
     Exp,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
 }
 
 impl Token {
@@ -40,11 +50,19 @@ impl Token {
             Name(Default::default()),
             OpenParen,
             CloseParen,
+            Comma,
+            Bar,
             Add,
             Sub,
             Mul,
             Div,
             Exp,
+            Lt,
+            Le,
+            Gt,
+            Ge,
+            Eq,
+            Ne,
         ]
     }
 
@@ -59,49 +77,60 @@ impl Token {
             // Literal tokens.
             (OpenParen, "(") |
             (CloseParen, ")") |
+            (Comma, ",") |
+            (Bar, "|") |
             (Add, "+") |
             (Sub, "-") |
             (Mul, "*") |
             (Div, "/") |
-            (Exp, "^") => true,
-
-            // Numeric tokens.
+            (Exp, "^") |
+            (Lt, "<") |
+            (Le, "<=") |
+            (Gt, ">") |
+            (Ge, ">=") |
+            (Eq, "==") |
+            (Ne, "!=") => true,
+
+            // Proper prefixes of multi-character literal tokens.
+            (Le, "<") |
+            (Ge, ">") |
+            (Eq, "=") |
+            (Ne, "!") => kind == MatchKind::Prefix,
+
+            // Numeric tokens: digits with an optional decimal point, which may lead (e.g. `.5`) or
+            // trail (e.g. `5.`), but must be accompanied by at least one digit.
             (Number(_), s) => {
-                #[derive(PartialEq)]
-                enum State { Integer, Dot, Fractional }
+                #[derive(Clone, Copy, PartialEq)]
+                enum State { Start, Integer, Dot, Fractional }
 
-                let mut state = State::Integer;
+                let mut state = State::Start;
                 s.chars().all(|c| {
-                    match state {
-                        State::Integer => {
-                            if c == '.' {
-                                state = State::Dot;
-                                true
-                            } else {
-                                c.is_digit(10)
-                            }
-                        }
-                        State::Dot => {
-                            state = State::Fractional;
-                            c.is_digit(10)
+                    state = match (state, c) {
+                        (State::Start, '.') => State::Dot,
+                        (State::Start, c) | (State::Integer, c) if c.is_digit(10) => State::Integer,
+                        (State::Integer, '.') => State::Fractional,
+                        (State::Dot, c) | (State::Fractional, c) if c.is_digit(10) => {
+                            State::Fractional
                         }
-                        State::Fractional => c.is_digit(10),
-                    }
+                        _ => return false,
+                    };
+                    true
                 }) && (kind == MatchKind::Prefix || state != State::Dot)
             }
 
             // Textual tokens (e.g. variables and functions).
-            (Name(_), s) => {
-                s.chars().all(|c| {
-                    c.is_ascii_alphabetic() && c.is_ascii_lowercase() || c == 'π' || c == 'τ'
-                })
-            }
+            (Name(_), s) => s.chars().all(|c| c.is_ascii_alphabetic() || is_greek(c)),
 
             _ => false,
         }
     }
 }
 
+/// Whether a character is a (lowercase or uppercase) Greek letter.
+fn is_greek(c: char) -> bool {
+    matches!(c, 'α' ..= 'ω' | 'Α' ..= 'Ω')
+}
+
 /// A token together with the string to which it corresponds.
 #[derive(Debug)]
 pub struct Lexeme {
@@ -113,20 +142,32 @@ pub struct Lexeme {
 pub struct Lexer;
 
 impl Lexer {
-    /// Convert a stream of characters into a stream of lexemes.
+    /// Convert a stream of characters into a stream of lexemes. Errors about a particular symbol
+    /// give its (zero-indexed) character position, and underline it in the input. A `#` begins a
+    /// comment, which extends to the end of the input.
     pub fn scan(chars: impl Iterator<Item = char>) -> Result<Vec<Lexeme>, String> {
+        let input: String = chars.collect();
         let mut lexemes = vec![];
-        let mut chars = chars.peekable();
+        let mut chars = input.chars().peekable();
+        // The number of characters consumed so far.
+        let mut position = 0;
         let mut end = false;
 
         while !end {
             let mut s = String::new();
             let mut states = Token::all();
+            let start = position;
 
             end = loop {
                 if let Some(&c) = chars.peek() {
+                    // No token contains `#`, so a comment also terminates any token preceding it,
+                    // as the end of the input would.
+                    if c == '#' {
+                        break true;
+                    }
                     if c.is_ascii_whitespace() {
                         chars.next();
+                        position += 1;
                         break false;
                     }
 
@@ -138,6 +179,7 @@ impl Lexer {
                         .collect();
                     if !states_next.is_empty() || s.is_empty() {
                         chars.next();
+                        position += 1;
                         s = s_next;
                         states = states_next;
                     } else {
@@ -157,7 +199,10 @@ impl Lexer {
                 let mut states = states.into_iter();
                 let first = states.next();
                 match (first, states.next()) {
-                    (None, _) => return Err(format!("unrecognised symbol {}", s)),
+                    (None, _) => {
+                        let message = format!("unrecognised symbol {}", s);
+                        return Err(Self::locate(&message, &input, start, &s));
+                    }
                     (Some(state), None) => {
                         lexemes.push(Lexeme {
                             kind: state,
@@ -165,7 +210,15 @@ impl Lexer {
                         });
                     }
                     _ if end => return Err("unexpected end of input".to_string()),
-                    _ => panic!("ambiguous token".to_string()),
+                    // This can only happen if two kinds of token match the same string, which is a
+                    // flaw in the token definitions, but we still want to report it gracefully.
+                    (Some(first), Some(second)) => {
+                        let kinds: Vec<_> = vec![first, second].into_iter().chain(states)
+                            .map(|kind| format!("{:?}", kind))
+                            .collect();
+                        let message = format!("ambiguous symbol {} ({})", s, kinds.join(", "));
+                        return Err(Self::locate(&message, &input, start, &s));
+                    }
                 }
             }
         }
@@ -178,6 +231,20 @@ impl Lexer {
         Ok(lexemes)
     }
 
+    /// Append the position of the symbol `s`, starting at character `start`, to an error message,
+    /// along with the input with the symbol underlined.
+    fn locate(message: &str, input: &str, start: usize, s: &str) -> String {
+        format!(
+            "{} at position {}\n{}\n{}{}",
+            message,
+            start,
+            input,
+            // Tabs are preserved so that the underline is aligned however they are displayed.
+            input.chars().take(start).map(|c| if c == '\t' { c } else { ' ' }).collect::<String>(),
+            "^".repeat(s.chars().count()),
+        )
+    }
+
     pub fn evaluate(lexemes: impl Iterator<Item = Lexeme>) -> impl Iterator<Item = Token> {
         lexemes.map(|l| {
             match l.kind {
@@ -191,12 +258,34 @@ impl Lexer {
 
 type ParseResult<T> = Result<T, ()>;
 
+/// The default maximum depth to which expressions may be nested. Parsing and evaluation are
+/// recursive, so deeper expressions could overflow the stack: each level of nesting uses several
+/// kilobytes of stack when parsing, and a WASM module has only 1 MiB by default.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// The default maximum number of binary operators in an expression. Chains of operators (e.g.
+/// `1 + 1 + ... + 1`) are parsed without nesting, but produce expressions whose depth grows with
+/// their length, which could overflow the stack when they are evaluated.
+pub const DEFAULT_MAX_LENGTH: usize = 1024;
+
 /// A parser for expressions.
 #[derive(Clone, Debug)]
 pub struct Parser<I: Iterator<Item = Token> + Clone> {
     tokens: I,
     pos: usize,
     token: Token,
+    /// The depth of nesting of the expression currently being parsed.
+    depth: usize,
+    max_depth: usize,
+    /// Whether parsing failed because the expression was nested more deeply than `max_depth`.
+    /// This persists across backtracking.
+    exceeded_max_depth: bool,
+    /// The number of binary operators parsed so far.
+    length: usize,
+    max_length: usize,
+    /// Whether parsing failed because the expression had more than `max_length` binary operators.
+    /// This persists across backtracking.
+    exceeded_max_length: bool,
 }
 
 impl Parser<IntoIter<Token>> {
@@ -207,6 +296,12 @@ impl Parser<IntoIter<Token>> {
                 tokens,
                 pos: 1,
                 token,
+                depth: 0,
+                max_depth: DEFAULT_MAX_DEPTH,
+                exceeded_max_depth: false,
+                length: 0,
+                max_length: DEFAULT_MAX_LENGTH,
+                exceeded_max_length: false,
             }
         } else {
             panic!("parser given no tokens");
@@ -217,6 +312,7 @@ impl Parser<IntoIter<Token>> {
 /// The various precedences for operations.
 #[derive(PartialEq, PartialOrd, Clone, Copy, Debug)]
 enum Precedence {
+    Relational,
     Additive,
     Multiplicative,
     Exponential,
@@ -225,12 +321,13 @@ enum Precedence {
 impl Precedence {
     /// The lowest precedence level (i.e. the one that binds least tightly).
     fn lowest() -> Precedence {
-        Precedence::Additive
+        Precedence::Relational
     }
 
     /// The next highest precedence, or `None` if there are no higher precedence levels.
     fn next(&self) -> Option<Precedence> {
         Some(match self {
+            Precedence::Relational => Precedence::Additive,
             Precedence::Additive => Precedence::Multiplicative,
             Precedence::Multiplicative => Precedence::Exponential,
             Precedence::Exponential => return None,
@@ -240,6 +337,7 @@ impl Precedence {
     /// Whether operators of this precedence are left-associative.
     fn left_associative(&self) -> bool {
         match self {
+            Precedence::Relational |
             Precedence::Additive |
             Precedence::Multiplicative => true,
 
@@ -253,15 +351,35 @@ pub enum Function {
     Sin,
     Cos,
     Tan,
+    Sec,
+    Csc,
+    Cot,
     Asin,
     Acos,
     Atan,
+    Asec,
+    Acsc,
+    /// The inverse of `cot`, taking values in `(-π/2, π/2]`.
+    Acot,
     Sinh,
     Cosh,
     Tanh,
     Asinh,
     Acosh,
     Atanh,
+    Floor,
+    Ceil,
+    /// Rounds half-way cases away from zero.
+    Round,
+    /// The absolute value, which may also be written `|x|`.
+    Abs,
+    /// The exponential function, `e^x`.
+    Exp,
+    /// The logarithm to base 10.
+    Log,
+    /// The sign of a number: `-1` if it is negative, `1` if it is positive and `0` if it is zero
+    /// (of either sign).
+    Sign,
 }
 
 impl FromStr for Function {
@@ -272,15 +390,28 @@ impl FromStr for Function {
             "sin" => Function::Sin,
             "cos" => Function::Cos,
             "tan" => Function::Tan,
+            "sec" => Function::Sec,
+            "csc" => Function::Csc,
+            "cot" => Function::Cot,
             "asin" => Function::Asin,
             "acos" => Function::Acos,
             "atan" => Function::Atan,
+            "asec" => Function::Asec,
+            "acsc" => Function::Acsc,
+            "acot" => Function::Acot,
             "sinh" => Function::Sinh,
             "cosh" => Function::Cosh,
             "tanh" => Function::Tanh,
             "asinh" => Function::Asinh,
             "acosh" => Function::Acosh,
             "atanh" => Function::Atanh,
+            "floor" => Function::Floor,
+            "ceil" => Function::Ceil,
+            "round" => Function::Round,
+            "abs" => Function::Abs,
+            "exp" => Function::Exp,
+            "log" => Function::Log,
+            "sign" => Function::Sign,
             _ => return Err(()),
         })
     }
@@ -292,15 +423,83 @@ impl fmt::Display for Function {
             Function::Sin => "sin",
             Function::Cos => "cos",
             Function::Tan => "tan",
+            Function::Sec => "sec",
+            Function::Csc => "csc",
+            Function::Cot => "cot",
             Function::Asin => "asin",
             Function::Acos => "acos",
             Function::Atan => "atan",
+            Function::Asec => "asec",
+            Function::Acsc => "acsc",
+            Function::Acot => "acot",
             Function::Sinh => "sinh",
             Function::Cosh => "cosh",
             Function::Tanh => "tanh",
             Function::Asinh => "asinh",
             Function::Acosh => "acosh",
             Function::Atanh => "atanh",
+            Function::Floor => "floor",
+            Function::Ceil => "ceil",
+            Function::Round => "round",
+            Function::Abs => "abs",
+            Function::Exp => "exp",
+            Function::Log => "log",
+            Function::Sign => "sign",
+        })
+    }
+}
+
+/// A mathematical function of two arguments.
+pub enum BinaryFunction {
+    /// The logarithm `log(b, x)` of `x` to the base `b`. Note that the base is the first argument.
+    Log,
+    /// The magnitude `norm(x, y)` of the vector `(x, y)`. This does not overflow or underflow when
+    /// `x^2` or `y^2` would.
+    Norm,
+}
+
+impl FromStr for BinaryFunction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "log" => BinaryFunction::Log,
+            "norm" => BinaryFunction::Norm,
+            _ => return Err(()),
+        })
+    }
+}
+
+impl fmt::Display for BinaryFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            BinaryFunction::Log => "log",
+            BinaryFunction::Norm => "norm",
+        })
+    }
+}
+
+/// A mathematical function of three arguments.
+pub enum TernaryFunction {
+    /// `clamp(x, lo, hi)` restricts `x` to the interval `[lo, hi]`. It is NaN if `lo > hi`.
+    Clamp,
+}
+
+impl FromStr for TernaryFunction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "clamp" => TernaryFunction::Clamp,
+            _ => return Err(()),
+        })
+    }
+}
+
+impl fmt::Display for TernaryFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            TernaryFunction::Clamp => "clamp",
         })
     }
 }
@@ -311,6 +510,18 @@ impl fmt::Debug for Function {
     }
 }
 
+impl fmt::Debug for BinaryFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl fmt::Debug for TernaryFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
 /// A handy macro while `try` is unavailable: returns the first `Err` or the trailing expression if
 /// `Ok`.
 macro_rules! try_block {
@@ -320,10 +531,58 @@ macro_rules! try_block {
 }
 
 impl<I: Iterator<Item = Token> + Clone> Parser<I> {
+    /// Set the maximum depth to which expressions may be nested, which is `DEFAULT_MAX_DEPTH`
+    /// unless set otherwise.
+    pub fn with_max_depth(self, max_depth: usize) -> Self {
+        Parser { max_depth, ..self }
+    }
+
+    /// Whether parsing failed because the expression was nested more deeply than permitted.
+    pub fn exceeded_max_depth(&self) -> bool {
+        self.exceeded_max_depth
+    }
+
+    /// Set the maximum number of binary operators an expression may contain, which is
+    /// `DEFAULT_MAX_LENGTH` unless set otherwise.
+    pub fn with_max_length(self, max_length: usize) -> Self {
+        Parser { max_length, ..self }
+    }
+
+    /// Whether parsing failed because the expression contained more binary operators than
+    /// permitted.
+    pub fn exceeded_max_length(&self) -> bool {
+        self.exceeded_max_length
+    }
+
     fn err<T>() -> ParseResult<T> {
         Err(())
     }
 
+    /// Parse a subexpression one level more deeply nested, failing if this exceeds the maximum
+    /// depth. Once the maximum depth has been exceeded, every nested parse fails, so that
+    /// backtracking does not reparse the expression.
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> ParseResult<T>) -> ParseResult<T> {
+        if self.exceeded_max_depth || self.depth >= self.max_depth {
+            self.exceeded_max_depth = true;
+            return Self::err();
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Count another binary operator in the expression, failing if this exceeds the maximum
+    /// length. Like `nested`, every subsequent operator fails once the maximum has been exceeded.
+    fn lengthen(&mut self) -> ParseResult<()> {
+        if self.exceeded_max_length || self.length >= self.max_length {
+            self.exceeded_max_length = true;
+            return Self::err();
+        }
+        self.length += 1;
+        Ok(())
+    }
+
     /// Advance a single token.
     fn bump(&mut self) {
         if let Token::End = self.token {
@@ -368,7 +627,11 @@ impl<I: Iterator<Item = Token> + Clone> Parser<I> {
 
     /// Load a previously-saved parser state for backtracking.
     fn restore(&mut self, save: Self) {
+        let (exceeded_max_depth, exceeded_max_length) =
+            (self.exceeded_max_depth, self.exceeded_max_length);
         mem::replace(self, save);
+        self.exceeded_max_depth |= exceeded_max_depth;
+        self.exceeded_max_length |= exceeded_max_length;
     }
 
     /// The top-level parsing method.
@@ -387,19 +650,17 @@ impl<I: Iterator<Item = Token> + Clone> Parser<I> {
     fn parse_expr_with_precedence(&mut self, precedence: Option<Precedence>) -> ParseResult<Expr> {
         if let Some(precedence) = precedence {
             let mut subexpr = self.parse_op_expr(precedence)?;
-            let mut expr_suffix = self.parse_expr_suffix(precedence)?;
+            let suffix = self.parse_expr_suffix(precedence)?;
 
             if precedence.left_associative() {
-                while let ExprSuffix::Chain { op, expr, suffix } = expr_suffix {
+                for (op, expr) in suffix {
                     subexpr = Expr::BinOp(op, box subexpr, box expr);
-                    expr_suffix = *suffix;
                 }
             } else {
                 let mut chain = vec![];
-                while let ExprSuffix::Chain { op, expr, suffix } = expr_suffix {
+                for (op, expr) in suffix {
                     chain.push((op, subexpr));
                     subexpr = expr;
-                    expr_suffix = *suffix;
                 }
                 while let Some((op, expr)) = chain.pop() {
                     subexpr = Expr::BinOp(op, box expr, box subexpr);
@@ -408,36 +669,63 @@ impl<I: Iterator<Item = Token> + Clone> Parser<I> {
 
             Ok(subexpr)
         } else {
-            self.parse_term()
+            // Terms may contain arbitrary expressions (e.g. in parentheses).
+            self.nested(Self::parse_term)
         }
     }
 
-    // E_i' ::= O E_i E_i' | empty
-    fn parse_expr_suffix(&mut self, precedence: Precedence) -> ParseResult<ExprSuffix> {
-        let save = self.save();
-
-        let op_expr: ParseResult<_> = try_block! {
-            ExprSuffix::Chain {
-                op: self.parse_bin_op(precedence)?,
-                expr: self.parse_expr_with_precedence(precedence.next())?,
-                suffix: box self.parse_expr_suffix(precedence)?,
+    // E_i' ::= O E_{i + 1} E_i' | empty   (left-associative)
+    // E_i' ::= O P_i E_i' | empty         (right-associative)
+    //
+    // The suffix is a chain of operators and operands, which allows us to derive left-associative
+    // expressions while avoiding left recursion. It is parsed iteratively rather than recursively,
+    // so that long chains (e.g. `1 + 1 + ... + 1`) are limited by the maximum length, rather than
+    // by the maximum depth.
+    fn parse_expr_suffix(&mut self, precedence: Precedence) -> ParseResult<Vec<(BinOp, Expr)>> {
+        let mut chain = vec![];
+        loop {
+            let save = self.save();
+            let link: ParseResult<_> = try_block! {
+                let op = self.parse_bin_op(precedence)?;
+                self.lengthen()?;
+                // Prefix operators are permitted on exponents, so that `2^-3` is `2^(-3)`.
+                let expr = if precedence.left_associative() {
+                    self.parse_expr_with_precedence(precedence.next())?
+                } else {
+                    self.parse_op_expr(precedence)?
+                };
+                (op, expr)
+            };
+            match link {
+                Ok(link) => chain.push(link),
+                Err(()) => {
+                    self.restore(save);
+                    return Ok(chain);
+                }
             }
-        };
-
-        op_expr.or_else(|_| {
-            self.restore(save);
-            Ok(ExprSuffix::Empty)
-        })
+        }
     }
 
-    // P_i ::= U E_i | E_i
+    // P_i ::= U P_i | E_{i + 1}   (additive)
+    // P_i ::= U E_i | E_{i + 1}   (exponential)
+    // P_i ::= E_{i + 1}           (otherwise)
+    //
+    // Exponentiation binds more tightly than a prefix operator on its base, so `-2^2` is
+    // `-(2^2)`, whereas a prefix operator on an exponent extends over the rest of the exponent, so
+    // `2^-3^2` is `2^(-(3^2))`.
     fn parse_op_expr(&mut self, precedence: Precedence) -> ParseResult<Expr> {
-        let prefix_op = self.parse_prefix_un_op(precedence);
-        let subexpr = self.parse_expr_with_precedence(precedence.next())?;
-        if let Ok(op) = prefix_op {
+        if let Ok(op) = self.parse_prefix_un_op(precedence) {
+            let subexpr = self.nested(|this| match precedence {
+                // Prefix operators may be repeated (e.g. `-+-t`), and their operand extends over
+                // any multiplication, but not addition (e.g. `-a + b` is `(-a) + b`).
+                Precedence::Additive => this.parse_op_expr(precedence),
+                // Prefix operators on the operands of multiplication (e.g. `3 * -2^2`) bind less
+                // tightly than exponentiation, as at the start of an expression.
+                _ => this.parse_expr_with_precedence(Some(precedence)),
+            })?;
             Ok(Expr::UnOp(op, box subexpr))
         } else {
-            Ok(subexpr)
+            self.parse_expr_with_precedence(precedence.next())
         }
     }
 
@@ -451,28 +739,35 @@ impl<I: Iterator<Item = Token> + Clone> Parser<I> {
         Self::err()
     }
 
-    // O ::= + | - | * | / | ^
+    // O ::= R | + | - | * | / | ^
     fn parse_bin_op(&mut self, precedence: Precedence) -> ParseResult<BinOp> {
         self.parse_op(match precedence {
+            Precedence::Relational => {
+                Comparison::all().into_iter().map(|(t, op)| (t, BinOp::Compare(op))).collect()
+            }
             Precedence::Additive => vec![(Token::Add, BinOp::Add), (Token::Sub, BinOp::Sub)],
             Precedence::Multiplicative => vec![(Token::Mul, BinOp::Mul), (Token::Div, BinOp::Div)],
             Precedence::Exponential => vec![(Token::Exp, BinOp::Exp)],
         })
     }
 
-    // U ::= -
+    // U ::= - | +
     fn parse_prefix_un_op(&mut self, precedence: Precedence) -> ParseResult<UnOp> {
+        let prefix_ops = vec![(Token::Sub, UnOp::Minus), (Token::Add, UnOp::Plus)];
         self.parse_op(match precedence {
-            Precedence::Additive => vec![(Token::Sub, UnOp::Minus)],
+            Precedence::Relational => vec![],
+            Precedence::Additive => prefix_ops,
             Precedence::Multiplicative => vec![],
-            Precedence::Exponential => vec![],
+            Precedence::Exponential => prefix_ops,
         })
     }
 
-    // T ::= ( E ) | V | X
+    // T ::= ( E_0 ) | '|' E_0 '|' | W | F | V | X
     fn parse_term(&mut self) -> ParseResult<Expr> {
         let save1 = self.save();
         let save2 = self.save();
+        let save3 = self.save();
+        let save4 = self.save();
 
         let parenthesised_expr: ParseResult<_> = try_block! {
             self.eat(Token::OpenParen)?;
@@ -483,9 +778,23 @@ impl<I: Iterator<Item = Token> + Clone> Parser<I> {
 
         parenthesised_expr.or_else(|_| {
             self.restore(save1);
-            self.parse_function()
+            // A bar that is not the start of a term closes the innermost absolute value, so
+            // `||x| - 1|` is `abs(abs(x) - 1)`.
+            let abs: ParseResult<_> = try_block! {
+                self.eat(Token::Bar)?;
+                let expr = self.parse_expr()?;
+                self.eat(Token::Bar)?;
+                Expr::Function(Function::Abs, box expr)
+            };
+            abs
+        }).or_else(|_| {
+            self.restore(save4);
+            self.parse_piecewise()
         }).or_else(|_| {
             self.restore(save2);
+            self.parse_function()
+        }).or_else(|_| {
+            self.restore(save3);
             self.parse_var()
         }).or_else(|_| {
             self.parse_value()
@@ -494,26 +803,73 @@ impl<I: Iterator<Item = Token> + Clone> Parser<I> {
         })
     }
 
-    // F ::= ('a' ..= 'z')+ ( E_0 )
-    fn parse_function(&mut self) -> ParseResult<Expr> {
-        let f = match self.token {
-            Token::Name(ref n) if n.len() > 1 => {
-                Function::from_str(&n)?
+    // W ::= if C then E_0 | if C then E_0 else E_0
+    fn parse_piecewise(&mut self) -> ParseResult<Expr> {
+        self.eat(Token::Name("if".to_string()))?;
+        let condition = self.parse_condition()?;
+        self.eat(Token::Name("then".to_string()))?;
+        let mut branches = vec![(condition, self.parse_expr()?)];
+        if self.eat(Token::Name("else".to_string())).is_ok() {
+            // Chains of `else if` are flattened into a single piecewise expression.
+            match self.parse_expr()? {
+                Expr::Piecewise(rest) => branches.extend(rest),
+                expr => branches.push((Condition::Always, expr)),
             }
+        }
+        Ok(Expr::Piecewise(branches))
+    }
+
+    // C ::= E_1 R E_1
+    fn parse_condition(&mut self) -> ParseResult<Condition> {
+        // The operands bind more tightly than comparisons, so that the comparison is not parsed as
+        // part of the left-hand side.
+        let operand = Precedence::lowest().next();
+        let lhs = self.parse_expr_with_precedence(operand)?;
+        let op = self.parse_op(Comparison::all())?;
+        let rhs = self.parse_expr_with_precedence(operand)?;
+        Ok(Condition::Compare(op, box lhs, box rhs))
+    }
+
+    // F ::= ('a' ..= 'z')+ ( E_0 [, E_0 [, E_0]] )
+    fn parse_function(&mut self) -> ParseResult<Expr> {
+        let name = match self.token {
+            Token::Name(ref n) if n.len() > 1 => n.clone(),
             _ => return Self::err(),
         };
         self.bump();
         self.eat(Token::OpenParen)?;
         let expr = self.parse_expr()?;
+        // The number of arguments determines which function is named, as some names (e.g. `log`)
+        // are shared by functions of different arities.
+        let expr = if let Token::Comma = self.token {
+            self.bump();
+            let arg = self.parse_expr()?;
+            if let Token::Comma = self.token {
+                self.bump();
+                let last = self.parse_expr()?;
+                let function = TernaryFunction::from_str(&name)?;
+                Expr::TernaryFunction(function, box expr, box arg, box last)
+            } else {
+                Expr::BinaryFunction(BinaryFunction::from_str(&name)?, box expr, box arg)
+            }
+        } else {
+            Expr::Function(Function::from_str(&name)?, box expr)
+        };
         self.eat(Token::CloseParen)?;
-        Ok(Expr::Function(f, box expr))
+        Ok(expr)
     }
 
-    /// Parse a variable: a single alphabetic character.
+    /// Parse a variable: a single Latin or Greek letter (of either case) that is not a constant.
+    /// Variables are case-sensitive.
     fn parse_var(&mut self) -> ParseResult<Expr> {
         let n = match self.token {
-            Token::Name(ref n) if n.chars().next().map_or(false, |c| c.is_ascii_alphabetic()) => {
-                n.clone()
+            Token::Name(ref n) => {
+                let mut chars = n.chars();
+                match (chars.next(), chars.next()) {
+                    (Some('π'), None) | (Some('τ'), None) => return Self::err(),
+                    (Some(_), None) => n.clone(),
+                    _ => return Self::err(),
+                }
             }
             _ => return Self::err(),
         };
@@ -521,14 +877,16 @@ impl<I: Iterator<Item = Token> + Clone> Parser<I> {
         Ok(Expr::Var(n))
     }
 
-    /// Parse a numeric value (integral or floating-point).
+    /// Parse a numeric value (integral or floating-point), or a constant. The constants may be
+    /// spelled out (`pi` and `tau`), which cannot shadow variables, as variables are single
+    /// letters.
     fn parse_value(&mut self) -> ParseResult<Expr> {
         let v = match self.token {
             Token::Number(v) => v,
             Token::Name(ref n) => {
                 match n.as_str() {
-                    "π" => f64::consts::PI,
-                    "τ" => f64::consts::PI * 2.0,
+                    "π" | "pi" => f64::consts::PI,
+                    "τ" | "tau" => f64::consts::PI * 2.0,
                     _ => return Self::err(),
                 }
             }
@@ -543,6 +901,7 @@ impl<I: Iterator<Item = Token> + Clone> Parser<I> {
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum UnOp {
     Minus, // `-`
+    Plus, // `+`
 }
 
 /// The binary operators.
@@ -553,6 +912,90 @@ pub enum BinOp {
     Mul, // `*`
     Div, // `/`
     Exp, // `^`
+    /// A comparison, which evaluates to `1` if it holds and `0` otherwise (see
+    /// `Comparison::holds`), e.g. so that a figure may be masked by multiplying by `(t > 0)`.
+    Compare(Comparison),
+}
+
+/// The comparison operators.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Comparison {
+    Lt, // `<`
+    Le, // `<=`
+    Gt, // `>`
+    Ge, // `>=`
+    Eq, // `==`
+    Ne, // `!=`
+}
+
+impl Comparison {
+    /// The comparison operators, paired with their tokens.
+    fn all() -> Vec<(Token, Comparison)> {
+        vec![
+            (Token::Lt, Comparison::Lt),
+            (Token::Le, Comparison::Le),
+            (Token::Gt, Comparison::Gt),
+            (Token::Ge, Comparison::Ge),
+            (Token::Eq, Comparison::Eq),
+            (Token::Ne, Comparison::Ne),
+        ]
+    }
+
+    /// Whether the comparison holds. This follows IEEE 754: every comparison involving NaN is
+    /// false, except for `!=`, which is true.
+    pub fn holds(self, lhs: f64, rhs: f64) -> bool {
+        match self {
+            Comparison::Lt => lhs < rhs,
+            Comparison::Le => lhs <= rhs,
+            Comparison::Gt => lhs > rhs,
+            Comparison::Ge => lhs >= rhs,
+            Comparison::Eq => lhs == rhs,
+            Comparison::Ne => lhs != rhs,
+        }
+    }
+
+    /// Whether the comparison holds between complex numbers. Complex numbers may be compared for
+    /// (in)equality, but are only ordered if both are real.
+    pub fn holds_complex(self, lhs: Complex<f64>, rhs: Complex<f64>) -> bool {
+        match self {
+            Comparison::Eq => lhs == rhs,
+            Comparison::Ne => lhs != rhs,
+            _ if lhs.im == 0.0 && rhs.im == 0.0 => self.holds(lhs.re, rhs.re),
+            _ => false,
+        }
+    }
+
+    /// The symbol with which the comparison is written.
+    fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+            Comparison::Eq => "==",
+            Comparison::Ne => "!=",
+        }
+    }
+
+    /// The LaTeX symbol corresponding to the comparison.
+    fn to_latex(self) -> &'static str {
+        match self {
+            Comparison::Lt => "<",
+            Comparison::Le => "\\leq",
+            Comparison::Gt => ">",
+            Comparison::Ge => "\\geq",
+            Comparison::Eq => "=",
+            Comparison::Ne => "\\neq",
+        }
+    }
+}
+
+/// A condition guarding a branch of a piecewise expression.
+#[derive(Debug)]
+pub enum Condition {
+    Compare(Comparison, Box<Expr>, Box<Expr>),
+    /// The condition of a trailing `else` branch.
+    Always,
 }
 
 /// A mathematical expression.
@@ -563,19 +1006,11 @@ pub enum Expr {
     UnOp(UnOp, Box<Expr>),
     BinOp(BinOp, Box<Expr>, Box<Expr>),
     Function(Function, Box<Expr>),
-}
-
-/// An expression suffix represents a chain of operators and subexpressions, allowing us to parse
-/// chains of left-associative operators and operands. This is necessary to derive left-associative
-/// expressions while avoiding left recursion.
-#[derive(Debug)]
-enum ExprSuffix {
-    Chain {
-        op: BinOp,
-        expr: Expr,
-        suffix: Box<ExprSuffix>,
-    },
-    Empty,
+    BinaryFunction(BinaryFunction, Box<Expr>, Box<Expr>),
+    TernaryFunction(TernaryFunction, Box<Expr>, Box<Expr>, Box<Expr>),
+    /// A piecewise expression, written `if C then E else ...`, whose value is that of the first
+    /// branch whose condition holds, or NaN if there is no such branch.
+    Piecewise(Vec<(Condition, Expr)>),
 }
 
 impl Expr {
@@ -583,11 +1018,17 @@ impl Expr {
     /// The two `bindings` correspond to those bindings that are constant, versus those that
     /// change frequently. From the perspective of `evaluate`, there's not a difference, but
     /// it avoids unnecessary `clone`s or implementing a delta `HashMap`.
+    ///
+    /// Functions applied outside their domains (e.g. `asin(2)` or `acosh(0)`) evaluate to NaN
+    /// rather than being clamped, and NaN propagates through the rest of the expression (except
+    /// through comparisons, which are `0` or `1`). The approximators ignore NaN points. Functions
+    /// applied at their singularities (e.g. `cot(0)`) evaluate to an infinity, which generally
+    /// becomes NaN when combined with other points.
     pub fn evaluate(&self, bindings: (&HashMap<char, f64>, &HashMap<char, f64>)) -> f64 {
         match self {
             &Expr::Number(x) => x,
             Expr::Var(v) => {
-                assert_eq!(v.len(), 1);
+                assert_eq!(v.chars().count(), 1);
                 let name = v.chars().next().unwrap();
                 if let Some(&x) = bindings.0.get(&name).or(bindings.1.get(&name)) {
                     x
@@ -599,6 +1040,7 @@ impl Expr {
                 let x = x.evaluate(bindings);
                 match op {
                     UnOp::Minus => -x,
+                    UnOp::Plus => x,
                 }
             }
             Expr::BinOp(op, lhs, rhs) => {
@@ -610,6 +1052,7 @@ impl Expr {
                     BinOp::Mul => lhs * rhs,
                     BinOp::Div => lhs / rhs,
                     BinOp::Exp => lhs.powf(rhs),
+                    BinOp::Compare(op) => if op.holds(lhs, rhs) { 1.0 } else { 0.0 },
                 }
             }
             Expr::Function(f, x) => {
@@ -618,19 +1061,323 @@ impl Expr {
                     Function::Sin => x.sin(),
                     Function::Cos => x.cos(),
                     Function::Tan => x.tan(),
+                    // The reciprocal functions are infinite where the denominator is zero.
+                    Function::Sec => 1.0 / x.cos(),
+                    Function::Csc => 1.0 / x.sin(),
+                    Function::Cot => 1.0 / x.tan(),
                     Function::Asin => x.asin(),
                     Function::Acos => x.acos(),
                     Function::Atan => x.atan(),
+                    Function::Asec => (1.0 / x).acos(),
+                    Function::Acsc => (1.0 / x).asin(),
+                    Function::Acot => (1.0 / x).atan(),
                     Function::Sinh => x.sinh(),
                     Function::Cosh => x.cosh(),
                     Function::Tanh => x.tanh(),
                     Function::Asinh => x.asinh(),
                     Function::Acosh => x.acosh(),
                     Function::Atanh => x.atanh(),
+                    Function::Floor => x.floor(),
+                    Function::Ceil => x.ceil(),
+                    Function::Round => x.round(),
+                    Function::Abs => x.abs(),
+                    Function::Exp => x.exp(),
+                    Function::Log => x.log10(),
+                    // `signum` is `±1` for `±0`, but we want the sign of zero to be zero.
+                    Function::Sign => if x == 0.0 { 0.0 } else { x.signum() },
                 }
             }
+            Expr::BinaryFunction(f, x, y) => {
+                let (x, y) = (x.evaluate(bindings), y.evaluate(bindings));
+                match f {
+                    // The common bases are special-cased, as they are more accurate than the
+                    // general logarithm (e.g. `log(10, 1000)` is exactly `3`).
+                    BinaryFunction::Log if x == 2.0 => y.log2(),
+                    BinaryFunction::Log if x == 10.0 => y.log10(),
+                    BinaryFunction::Log => y.log(x),
+                    BinaryFunction::Norm => x.hypot(y),
+                }
+            }
+            Expr::TernaryFunction(f, x, y, z) => {
+                let (x, y, z) = (x.evaluate(bindings), y.evaluate(bindings), z.evaluate(bindings));
+                match f {
+                    TernaryFunction::Clamp if y <= z => {
+                        // Unlike `max` and `min`, this propagates NaN.
+                        if x < y { y } else if x > z { z } else { x }
+                    }
+                    TernaryFunction::Clamp => f64::NAN,
+                }
+            }
+            Expr::Piecewise(branches) => {
+                branches.iter()
+                    .find(|(condition, _)| condition.evaluate(bindings))
+                    .map_or(f64::NAN, |(_, expr)| expr.evaluate(bindings))
+            }
+        }
+    }
+
+    /// Evaluate an expression over the complex numbers, given a set of variable bindings, e.g. to
+    /// apply a conformal map. Functions take their principal values. `floor`, `ceil` and `round`
+    /// apply to the real and imaginary parts separately, and `sign(z)` is `z / |z|`.
+    pub fn eval_complex(&self, bindings: &HashMap<char, Complex<f64>>) -> Complex<f64> {
+        let one = Complex::new(1.0, 0.0);
+        match self {
+            &Expr::Number(x) => Complex::new(x, 0.0),
+            Expr::Var(v) => {
+                assert_eq!(v.chars().count(), 1);
+                let name = v.chars().next().unwrap();
+                if let Some(&z) = bindings.get(&name) {
+                    z
+                } else {
+                    panic!("no binding for {}", v);
+                }
+            }
+            Expr::UnOp(op, z) => {
+                let z = z.eval_complex(bindings);
+                match op {
+                    UnOp::Minus => -z,
+                    UnOp::Plus => z,
+                }
+            }
+            Expr::BinOp(op, lhs, rhs) => {
+                let lhs = lhs.eval_complex(bindings);
+                let rhs = rhs.eval_complex(bindings);
+                match op {
+                    BinOp::Add => lhs + rhs,
+                    BinOp::Sub => lhs - rhs,
+                    BinOp::Mul => lhs * rhs,
+                    BinOp::Div => lhs / rhs,
+                    // Integer and real powers are special-cased, as `powc` is inexact even for
+                    // `i^2` (and `0^2` would be NaN).
+                    BinOp::Exp if rhs.im == 0.0 && rhs.re.fract() == 0.0 && rhs.re.abs() < 1e9 => {
+                        lhs.powi(rhs.re as i32)
+                    }
+                    BinOp::Exp if rhs.im == 0.0 => lhs.powf(rhs.re),
+                    BinOp::Exp => lhs.powc(rhs),
+                    BinOp::Compare(op) => {
+                        if op.holds_complex(lhs, rhs) { one } else { Complex::new(0.0, 0.0) }
+                    }
+                }
+            }
+            Expr::Function(f, z) => {
+                let z = z.eval_complex(bindings);
+                match f {
+                    Function::Sin => z.sin(),
+                    Function::Cos => z.cos(),
+                    Function::Tan => z.tan(),
+                    Function::Sec => one / z.cos(),
+                    Function::Csc => one / z.sin(),
+                    Function::Cot => one / z.tan(),
+                    Function::Asin => z.asin(),
+                    Function::Acos => z.acos(),
+                    Function::Atan => z.atan(),
+                    Function::Asec => (one / z).acos(),
+                    Function::Acsc => (one / z).asin(),
+                    Function::Acot => (one / z).atan(),
+                    Function::Sinh => z.sinh(),
+                    Function::Cosh => z.cosh(),
+                    Function::Tanh => z.tanh(),
+                    Function::Asinh => z.asinh(),
+                    Function::Acosh => z.acosh(),
+                    Function::Atanh => z.atanh(),
+                    Function::Floor => Complex::new(z.re.floor(), z.im.floor()),
+                    Function::Ceil => Complex::new(z.re.ceil(), z.im.ceil()),
+                    Function::Round => Complex::new(z.re.round(), z.im.round()),
+                    Function::Abs => Complex::new(z.norm(), 0.0),
+                    Function::Exp => z.exp(),
+                    Function::Log => z.ln() / f64::consts::LN_10,
+                    Function::Sign => if z.norm() == 0.0 { z } else { z / z.norm() },
+                }
+            }
+            Expr::BinaryFunction(f, x, y) => {
+                let (x, y) = (x.eval_complex(bindings), y.eval_complex(bindings));
+                match f {
+                    BinaryFunction::Log => y.ln() / x.ln(),
+                    BinaryFunction::Norm => {
+                        // As with `hypot`, the arguments are scaled by the larger of their norms,
+                        // so that squaring them does not overflow (or underflow).
+                        let scale = x.norm().max(y.norm());
+                        if scale == 0.0 || scale.is_infinite() {
+                            Complex::new(scale, 0.0)
+                        } else {
+                            let (x, y) = (x / scale, y / scale);
+                            (x * x + y * y).sqrt() * scale
+                        }
+                    }
+                }
+            }
+            Expr::TernaryFunction(f, x, y, z) => {
+                let (x, y, z) =
+                    (x.eval_complex(bindings), y.eval_complex(bindings), z.eval_complex(bindings));
+                let real = x.im == 0.0 && y.im == 0.0 && z.im == 0.0;
+                match f {
+                    // Complex numbers are not ordered, so only real numbers may be clamped.
+                    TernaryFunction::Clamp if real && y.re <= z.re => {
+                        let (x, y, z) = (x.re, y.re, z.re);
+                        Complex::new(if x < y { y } else if x > z { z } else { x }, 0.0)
+                    }
+                    TernaryFunction::Clamp => Complex::new(f64::NAN, f64::NAN),
+                }
+            }
+            Expr::Piecewise(branches) => {
+                branches.iter()
+                    .find(|(condition, _)| condition.eval_complex(bindings))
+                    .map_or(Complex::new(f64::NAN, f64::NAN), |(_, expr)| {
+                        expr.eval_complex(bindings)
+                    })
+            }
+        }
+    }
+
+    /// Evaluate an expression in a single variable, `var`, at each of the `values`. This is
+    /// equivalent to calling `evaluate` for each value, but reuses a single map for the binding
+    /// of `var`, rather than allocating one per value.
+    pub fn evaluate_batch(
+        &self,
+        var: char,
+        values: &[f64],
+        static_bindings: &HashMap<char, f64>,
+    ) -> Vec<f64> {
+        let mut bindings = HashMap::with_capacity(1);
+        values.iter().map(|&x| {
+            bindings.insert(var, x);
+            self.evaluate((&bindings, static_bindings))
+        }).collect()
+    }
+
+    /// The variables referenced by the expression, each of which must be bound to evaluate it.
+    /// Constants, such as `π` and `τ`, are parsed as numbers, so are not included.
+    pub fn variables(&self) -> BTreeSet<char> {
+        let mut variables = BTreeSet::new();
+        self.collect_variables(&mut variables);
+        variables
+    }
+
+    /// The depth of the expression tree: `1` for a number or variable, and one more than the
+    /// deepest subexpression otherwise. Parsing limits the depth of expressions (see
+    /// `DEFAULT_MAX_DEPTH`), as it, evaluation and rendering are recursive.
+    pub fn depth(&self) -> usize {
+        1 + match self {
+            Expr::Number(_) | Expr::Var(_) => 0,
+            Expr::UnOp(_, x) | Expr::Function(_, x) => x.depth(),
+            Expr::BinOp(_, lhs, rhs) | Expr::BinaryFunction(_, lhs, rhs) => {
+                lhs.depth().max(rhs.depth())
+            }
+            Expr::TernaryFunction(_, x, y, z) => x.depth().max(y.depth()).max(z.depth()),
+            Expr::Piecewise(branches) => branches.iter().map(|(condition, expr)| {
+                let condition = match condition {
+                    Condition::Compare(_, lhs, rhs) => lhs.depth().max(rhs.depth()),
+                    Condition::Always => 0,
+                };
+                condition.max(expr.depth())
+            }).max().unwrap_or(0),
+        }
+    }
+
+    fn collect_variables(&self, variables: &mut BTreeSet<char>) {
+        match self {
+            Expr::Number(_) => {}
+            Expr::Var(v) => variables.extend(v.chars()),
+            Expr::UnOp(_, x) | Expr::Function(_, x) => x.collect_variables(variables),
+            Expr::BinOp(_, lhs, rhs) | Expr::BinaryFunction(_, lhs, rhs) => {
+                lhs.collect_variables(variables);
+                rhs.collect_variables(variables);
+            }
+            Expr::TernaryFunction(_, x, y, z) => {
+                x.collect_variables(variables);
+                y.collect_variables(variables);
+                z.collect_variables(variables);
+            }
+            Expr::Piecewise(branches) => {
+                for (condition, expr) in branches {
+                    if let Condition::Compare(_, lhs, rhs) = condition {
+                        lhs.collect_variables(variables);
+                        rhs.collect_variables(variables);
+                    }
+                    expr.collect_variables(variables);
+                }
+            }
+        }
+    }
+}
+
+impl Condition {
+    /// Evaluate a condition, given a set of variable bindings. Comparisons involving NaN are
+    /// false, except for `!=`.
+    pub fn evaluate(&self, bindings: (&HashMap<char, f64>, &HashMap<char, f64>)) -> bool {
+        match self {
+            Condition::Compare(op, lhs, rhs) => {
+                op.holds(lhs.evaluate(bindings), rhs.evaluate(bindings))
+            }
+            Condition::Always => true,
         }
     }
+
+    /// Evaluate a condition over the complex numbers, as in `Expr::eval_complex`.
+    pub fn eval_complex(&self, bindings: &HashMap<char, Complex<f64>>) -> bool {
+        match self {
+            Condition::Compare(op, lhs, rhs) => {
+                op.holds_complex(lhs.eval_complex(bindings), rhs.eval_complex(bindings))
+            }
+            Condition::Always => true,
+        }
+    }
+}
+
+/// The ways in which constructing an equation from source strings can fail.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    /// A string could not be lexed or parsed.
+    InvalidEquation(String),
+    /// An equation references variables that have not been bound.
+    UnboundVariables(String),
+    /// An expression is nested more deeply than `DEFAULT_MAX_DEPTH`.
+    TooDeeplyNested(String),
+    /// An expression has more binary operators than `DEFAULT_MAX_LENGTH`.
+    TooLong(String),
+}
+
+/// Check that every variable in the expressions `x(t)` and `y(t)` of a parametric equation is
+/// either one of the `parameters` or bound in `static_bindings`, as evaluating an unbound variable
+/// panics. `strings` are the sources of the expressions, for reporting.
+pub fn check_bound(
+    [x, y]: [&Expr; 2],
+    strings: [&str; 2],
+    parameters: &[char],
+    static_bindings: &HashMap<char, f64>,
+) -> Result<(), ParseError> {
+    let unbound: Vec<_> = (&x.variables() | &y.variables()).into_iter().filter(|v| {
+        !parameters.contains(v) && !static_bindings.contains_key(v)
+    }).map(|v| format!("`{}`", v)).collect();
+    if unbound.is_empty() {
+        Ok(())
+    } else {
+        Err(ParseError::UnboundVariables(
+            format!("{} in `{}`, `{}`", unbound.join(", "), strings[0], strings[1]),
+        ))
+    }
+}
+
+impl FromStr for Expr {
+    type Err = ParseError;
+
+    /// Lex and parse a string into an expression.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lexemes = Lexer::scan(s.chars()).map_err(ParseError::InvalidEquation)?;
+        let tokens = Lexer::evaluate(lexemes.into_iter()).collect();
+        let mut parser = Parser::new(tokens);
+        parser.parse().map_err(|_| if parser.exceeded_max_depth() {
+            ParseError::TooDeeplyNested(
+                format!("`{}` is nested more than {} levels deep", s, DEFAULT_MAX_DEPTH),
+            )
+        } else if parser.exceeded_max_length() {
+            ParseError::TooLong(
+                format!("`{}` has more than {} binary operators", s, DEFAULT_MAX_LENGTH),
+            )
+        } else {
+            ParseError::InvalidEquation(format!("could not parse `{}`", s))
+        })
+    }
 }
 
 impl fmt::Display for Expr {
@@ -641,6 +1388,7 @@ impl fmt::Display for Expr {
             Expr::UnOp(op, x) => {
                 let op = match op {
                     UnOp::Minus => "-",
+                    UnOp::Plus => "+",
                 };
                 write!(f, "({}{})", op, x)
             }
@@ -651,10 +1399,193 @@ impl fmt::Display for Expr {
                     BinOp::Mul => "*",
                     BinOp::Div => "/",
                     BinOp::Exp => "^",
+                    BinOp::Compare(op) => op.symbol(),
                 };
                 write!(f, "({} {} {})", lhs, op, rhs)
             }
             Expr::Function(fun, x) => write!(f, "{}({})", fun, x),
+            Expr::BinaryFunction(fun, x, y) => write!(f, "{}({}, {})", fun, x, y),
+            Expr::TernaryFunction(fun, x, y, z) => write!(f, "{}({}, {}, {})", fun, x, y, z),
+            Expr::Piecewise(branches) => {
+                write!(f, "(")?;
+                for (i, (condition, expr)) in branches.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " else ")?;
+                    }
+                    match condition {
+                        Condition::Always => write!(f, "{}", expr)?,
+                        condition => write!(f, "if {} then {}", condition, expr)?,
+                    }
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::Compare(op, lhs, rhs) => write!(f, "{} {} {}", lhs, op, rhs),
+            Condition::Always => write!(f, "true"),
+        }
+    }
+}
+
+impl Function {
+    /// The LaTeX command corresponding to the function.
+    fn to_latex(&self) -> &'static str {
+        match self {
+            Function::Sin => "\\sin",
+            Function::Cos => "\\cos",
+            Function::Tan => "\\tan",
+            Function::Sec => "\\sec",
+            Function::Csc => "\\csc",
+            Function::Cot => "\\cot",
+            Function::Asin => "\\arcsin",
+            Function::Acos => "\\arccos",
+            Function::Atan => "\\arctan",
+            Function::Asec => "\\operatorname{arcsec}",
+            Function::Acsc => "\\operatorname{arccsc}",
+            Function::Acot => "\\operatorname{arccot}",
+            Function::Sinh => "\\sinh",
+            Function::Cosh => "\\cosh",
+            Function::Tanh => "\\tanh",
+            Function::Asinh => "\\operatorname{arsinh}",
+            Function::Acosh => "\\operatorname{arcosh}",
+            Function::Atanh => "\\operatorname{artanh}",
+            Function::Floor => "\\operatorname{floor}",
+            Function::Ceil => "\\operatorname{ceil}",
+            Function::Round => "\\operatorname{round}",
+            Function::Abs => "\\operatorname{abs}",
+            Function::Exp => "\\exp",
+            Function::Log => "\\log_{10}",
+            Function::Sign => "\\operatorname{sgn}",
+        }
+    }
+}
+
+impl Expr {
+    /// Render the expression as LaTeX. Unlike the `Display` implementation, which is fully
+    /// parenthesised, parentheses are only emitted where the precedence of the operators requires
+    /// them.
+    pub fn to_latex(&self) -> String {
+        match self {
+            // Constants are parsed as numbers, so are recognised by their value.
+            Expr::Number(x) if *x == f64::consts::PI => "\\pi".to_string(),
+            Expr::Number(x) if *x == f64::consts::PI * 2.0 => "\\tau".to_string(),
+            Expr::Number(x) => format!("{}", x),
+            Expr::Var(v) => v.clone(),
+            Expr::UnOp(op, x) => {
+                let op = match op {
+                    UnOp::Minus => "-",
+                    UnOp::Plus => "+",
+                };
+                format!("{}{}", op, x.to_latex_operand(Precedence::Multiplicative))
+            }
+            Expr::BinOp(op, lhs, rhs) => {
+                match op {
+                    BinOp::Add | BinOp::Sub | BinOp::Mul => {
+                        let (op, precedence) = match op {
+                            BinOp::Add => ("+", Precedence::Additive),
+                            BinOp::Sub => ("-", Precedence::Additive),
+                            _ => ("\\cdot", Precedence::Multiplicative),
+                        };
+                        // All of these operators are left-associative, so the right-hand side must
+                        // bind more tightly than the operator itself.
+                        format!(
+                            "{} {} {}",
+                            lhs.to_latex_operand(precedence),
+                            op,
+                            rhs.to_latex_operand(precedence.next().unwrap()),
+                        )
+                    }
+                    BinOp::Compare(op) => Expr::comparison_to_latex(*op, lhs, rhs),
+                    // Fractions group their numerator and denominator implicitly.
+                    BinOp::Div => format!("\\frac{{{}}}{{{}}}", lhs.to_latex(), rhs.to_latex()),
+                    BinOp::Exp => {
+                        // Anything other than a single symbol would be ambiguous as a base.
+                        let base = match **lhs {
+                            Expr::Number(_) | Expr::Var(_) => lhs.to_latex(),
+                            _ => format!("\\left({}\\right)", lhs.to_latex()),
+                        };
+                        format!("{}^{{{}}}", base, rhs.to_latex())
+                    }
+                }
+            }
+            Expr::Function(Function::Abs, x) => format!("\\left|{}\\right|", x.to_latex()),
+            Expr::Function(fun, x) => format!("{}({})", fun.to_latex(), x.to_latex()),
+            Expr::BinaryFunction(BinaryFunction::Log, base, x) => {
+                format!("\\log_{{{}}}({})", base.to_latex(), x.to_latex())
+            }
+            Expr::BinaryFunction(BinaryFunction::Norm, x, y) => {
+                format!("\\left\\|({}, {})\\right\\|", x.to_latex(), y.to_latex())
+            }
+            Expr::TernaryFunction(TernaryFunction::Clamp, x, lo, hi) => format!(
+                "\\operatorname{{clamp}}({}, {}, {})",
+                x.to_latex(),
+                lo.to_latex(),
+                hi.to_latex(),
+            ),
+            Expr::Piecewise(branches) => {
+                let branches: Vec<_> = branches.iter().map(|(condition, expr)| {
+                    let condition = match condition {
+                        Condition::Compare(op, lhs, rhs) => {
+                            Expr::comparison_to_latex(*op, lhs, rhs)
+                        }
+                        Condition::Always => "\\text{otherwise}".to_string(),
+                    };
+                    format!("{} & {}", expr.to_latex(), condition)
+                }).collect();
+                format!("\\begin{{cases}} {} \\end{{cases}}", branches.join(" \\\\ "))
+            }
+        }
+    }
+
+    /// Render a comparison as LaTeX. Chains of comparisons such as `a < b < c` conventionally mean
+    /// `a < b` and `b < c`, so nested comparisons are always grouped.
+    fn comparison_to_latex(op: Comparison, lhs: &Expr, rhs: &Expr) -> String {
+        format!(
+            "{} {} {}",
+            lhs.to_latex_operand(Precedence::Additive),
+            op.to_latex(),
+            rhs.to_latex_operand(Precedence::Additive),
+        )
+    }
+
+    /// The precedence of the outermost operator of the expression when rendered as LaTeX, or
+    /// `None` if the expression is visually self-contained.
+    fn latex_precedence(&self) -> Option<Precedence> {
+        match self {
+            Expr::Number(_)
+            | Expr::Var(_)
+            | Expr::Function(..)
+            | Expr::BinaryFunction(..)
+            | Expr::TernaryFunction(..)
+            | Expr::Piecewise(_) => None,
+            Expr::UnOp(..) => Some(Precedence::Additive),
+            Expr::BinOp(op, ..) => match op {
+                BinOp::Compare(_) => Some(Precedence::Relational),
+                BinOp::Add | BinOp::Sub => Some(Precedence::Additive),
+                BinOp::Mul => Some(Precedence::Multiplicative),
+                BinOp::Div => None,
+                BinOp::Exp => Some(Precedence::Exponential),
+            },
+        }
+    }
+
+    /// Render the expression as LaTeX as the operand of an operator of the given precedence,
+    /// grouping it if it binds less tightly than the operator.
+    fn to_latex_operand(&self, precedence: Precedence) -> String {
+        match self.latex_precedence() {
+            Some(p) if p < precedence => format!("\\left({}\\right)", self.to_latex()),
+            _ => self.to_latex(),
         }
     }
 }
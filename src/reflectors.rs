@@ -1,122 +1,538 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::iter;
 
-use rstar::{primitives::Line, PointDistance, RTree};
+use num_traits::Float;
+use rstar::{primitives::Line, RTree};
 
-use crate::approximation::{Equation, Interval, View};
-use crate::spatial::{Pair, Point2D, Quad, RTreeObjectWithData};
+use crate::approximation::{Equation, Interval, MirrorSampling, OrdFloat, View};
+use crate::spatial::{Pair, Point2D, Quad, RTreeObjectWithData, Scalar};
+
+/// An optional callback reporting the fraction of an approximation that has been completed, which
+/// returns `false` to cancel the approximation, in which case the partial result is returned.
+pub type Progress<'a> = Option<&'a mut dyn FnMut(f64) -> bool>;
+
+/// Report that `done` of the `total` rows of an approximation have been processed, returning
+/// whether the approximation should continue.
+fn report_progress(progress: &mut Progress<'_>, done: usize, total: usize) -> bool {
+    match progress {
+        Some(callback) => callback(done as f64 / total as f64),
+        None => true,
+    }
+}
+
+/// A (reflection, figure, mirror) triple: the image of a figure point, the figure point itself, and
+/// the point on the mirror in which it was reflected.
+pub type ReflectionTriple<S = f64> = (Pair<S>, Pair<S>, Pair<S>);
+
+/// A consumer of reflection triples.
+pub type TripleSink<'a, S = f64> = &'a mut dyn FnMut(ReflectionTriple<S>);
+
+/// A consumer of reflection triples, each paired with the `(t, s)` parameters of the point that was
+/// reflected.
+pub type Sink<'a, S = f64> = &'a mut dyn FnMut(ReflectionTriple<S>, (f64, f64));
+
+/// The inputs to a `ReflectionApproximator`: the `figure` to be reflected in the `mirror`, given as
+/// a sampling of its equation, the mapping `sigma_tau` from `(t, s)` to the parameters along the
+/// mirror and its normals, and the intervals over which `t` and `s` are sampled.
+#[derive(Clone, Copy)]
+pub struct ReflectionInputs<'a, S: Scalar = f64> {
+    pub mirror: &'a Equation<'a, f64, Pair<S>>,
+    pub figure: &'a [Pair<S>],
+    pub sigma_tau: &'a Equation<'a, (f64, f64)>,
+    pub t_interval: &'a Interval,
+    pub s_interval: &'a Interval,
+}
 
 /// A `ReflectionApproximator` provides a method to approximate points lying along the reflection
-/// of a `figure` equation in a `mirror` equation.
-pub trait ReflectionApproximator {
+/// of a `figure` equation in a `mirror` equation, as described by `ReflectionInputs`. The figure is
+/// given as a sampling of the equation, so that the (potentially expensive) sampling may be shared
+/// with the caller. The `progress` callback, if any, is invoked once per `t` row.
+///
+/// Equations evaluate to NaN outside their domains, and to infinities at their singularities (see
+/// `Expr::evaluate`), so approximators must ignore figure points that are not finite, and must not
+/// produce reflections whose images are not finite.
+///
+/// The output of an approximator must be deterministic: the same inputs should always produce the
+/// same points in the same order, so that renders are reproducible. In particular, intermediate
+/// results should not be collected into a `HashMap` or `HashSet` if they are later iterated over.
+///
+/// The coördinates of points are of type `S`, which is `f64` unless a smaller or larger scalar type
+/// is required. The parameters `t` and `s` are always `f64`.
+pub trait ReflectionApproximator<S: Scalar = f64> {
+    /// The name of the method of approximation. For those methods accepted by
+    /// `render_reflection`, this is the name by which the method is selected.
+    fn name(&self) -> &'static str;
+
     /// Returns (reflection, figure, mirror) triples.
     fn approximate_reflection(
         &self,
-        mirror: &Equation<'_, f64>,
-        figure: &Equation<'_, f64>,
-        sigma_tau: &Equation<'_, (f64, f64)>,
-        interval: &Interval,
+        inputs: ReflectionInputs<'_, S>,
+        view: &View,
+        progress: Progress<'_>,
+    ) -> Vec<ReflectionTriple<S>> {
+        let mut reflection = vec![];
+        self.approximate_reflection_streaming(
+            inputs,
+            view,
+            progress,
+            &mut |triple| reflection.push(triple),
+        );
+        reflection
+    }
+
+    /// Passes (reflection, figure, mirror) triples to `sink` as they are found, rather than
+    /// collecting them, so that they need not all be held in memory at once.
+    fn approximate_reflection_streaming(
+        &self,
+        inputs: ReflectionInputs<'_, S>,
         view: &View,
-    ) -> Vec<(Point2D, Point2D, Point2D)>;
+        progress: Progress<'_>,
+        sink: TripleSink<'_, S>,
+    ) {
+        self.approximate_reflection_streaming_with_params(
+            inputs,
+            view,
+            progress,
+            &mut |triple, _| sink(triple),
+        )
+    }
+
+    /// Returns (reflection, figure, mirror) triples for the reflection of the `figure` in the
+    /// `mirror` and then in each of the `further_mirrors` in turn, like a kaleidoscope: the images
+    /// of each stage form the figure for the next, and the triples of the final stage are returned.
+    /// `sigma_tau` is applied at each stage relative to that stage's mirror, so the overall mapping
+    /// is the composition of the mappings at each stage. The `progress` callback is passed the
+    /// fraction of the entire chain completed.
+    fn approximate_reflection_chain(
+        &self,
+        inputs: ReflectionInputs<'_, S>,
+        further_mirrors: &[&Equation<'_, f64, Pair<S>>],
+        view: &View,
+        mut progress: Progress<'_>,
+    ) -> Vec<ReflectionTriple<S>> {
+        let mut figure = inputs.figure.to_vec();
+        let mut reflection = vec![];
+
+        let mirrors = iter::once(inputs.mirror).chain(further_mirrors.iter().cloned());
+        let stages = (further_mirrors.len() + 1) as f64;
+        for (i, mirror) in mirrors.enumerate() {
+            let mut cancelled = false;
+            let mut stage_progress = |done: f64| {
+                let proceed = match &mut progress {
+                    Some(callback) => callback((i as f64 + done) / stages),
+                    None => true,
+                };
+                cancelled = !proceed;
+                proceed
+            };
+
+            reflection = self.approximate_reflection(
+                ReflectionInputs { mirror, figure: &figure, ..inputs },
+                view,
+                Some(&mut stage_progress),
+            );
+            if cancelled {
+                break;
+            }
+            figure = reflection.iter().map(|&(image, _, _)| image).collect();
+        }
+
+        reflection
+    }
+
+    /// Returns (reflection, figure, mirror) triples, each paired with the `(t, s)` parameters of
+    /// the point that was reflected. This is primarily useful for debugging equations.
+    fn approximate_reflection_with_params(
+        &self,
+        inputs: ReflectionInputs<'_, S>,
+        view: &View,
+        progress: Progress<'_>,
+    ) -> Vec<(ReflectionTriple<S>, (f64, f64))> {
+        let mut reflection = vec![];
+        self.approximate_reflection_streaming_with_params(
+            inputs,
+            view,
+            progress,
+            &mut |triple, params| reflection.push((triple, params)),
+        );
+        reflection
+    }
+
+    /// Passes (reflection, figure, mirror) triples, each paired with the `(t, s)` parameters of
+    /// the point that was reflected, to `sink` as they are found.
+    fn approximate_reflection_streaming_with_params(
+        &self,
+        inputs: ReflectionInputs<'_, S>,
+        view: &View,
+        progress: Progress<'_>,
+        sink: Sink<'_, S>,
+    );
+}
+
+/// A `ReflectionApproximator` whose approximation does not depend on the view, which may therefore
+/// be invoked without one.
+pub trait ViewIndependentApproximator<S: Scalar = f64>: ReflectionApproximator<S> {
+    /// Returns (reflection, figure, mirror) triples, like `approximate_reflection`.
+    fn approximate_reflection_without_view(
+        &self,
+        inputs: ReflectionInputs<'_, S>,
+        progress: Progress<'_>,
+    ) -> Vec<ReflectionTriple<S>> {
+        let mut reflection = vec![];
+        self.approximate_reflection_streaming_without_view(
+            inputs,
+            progress,
+            &mut |triple, _| reflection.push(triple),
+        );
+        reflection
+    }
+
+    /// Passes (reflection, figure, mirror) triples, each paired with the `(t, s)` parameters of
+    /// the point that was reflected, to `sink` as they are found, like
+    /// `approximate_reflection_streaming_with_params`.
+    fn approximate_reflection_streaming_without_view(
+        &self,
+        inputs: ReflectionInputs<'_, S>,
+        progress: Progress<'_>,
+        sink: Sink<'_, S>,
+    );
+}
+
+/// The segments of the normals to the mirror at each `t` sampled by `mirror_sampling`, from
+/// `s_interval.start` to `s_interval.end`. These are the normals along which the approximators
+/// reflect points, which is useful for visualising how a reflection is constructed.
+pub fn normal_segments<S: Scalar>(
+    mirror: &Equation<'_, f64, Pair<S>>,
+    t_interval: &Interval,
+    s_interval: &Interval,
+    mirror_sampling: MirrorSampling,
+) -> Vec<(Pair<S>, Pair<S>)> {
+    mirror_sampling.sample(mirror, t_interval).into_iter().map(|t| {
+        let normal = mirror.normal(t);
+        ((normal.function)(s_interval.start), (normal.function)(s_interval.end))
+    }).collect()
+}
+
+/// Remove reflection triples whose images are within `tolerance` of the image of an earlier triple,
+/// which removes the near-duplicate points that arise from floating-point error. The images that
+/// are kept are bucketed into a grid with cells of size `tolerance`, so that only the neighbouring
+/// cells of each image need be searched. `tolerance` must be positive.
+pub fn deduplicate(
+    reflection: Vec<(Point2D, Point2D, Point2D)>,
+    tolerance: f64,
+) -> Vec<(Point2D, Point2D, Point2D)> {
+    let mut cells: HashMap<(i64, i64), Vec<Point2D>> = HashMap::new();
+    reflection.into_iter().filter(|&(image, _, _)| {
+        let [x, y] = image.map(|x| (x / tolerance).floor() as i64).into_inner();
+        // Any image within `tolerance` lies in the same cell or in one of the eight adjacent cells.
+        let duplicate = (x - 1..=x + 1).any(|i| (y - 1..=y + 1).any(|j| {
+            cells.get(&(i, j)).into_iter().flatten().any(|&kept| {
+                (kept - image).dot(kept - image) <= tolerance * tolerance
+            })
+        }));
+        if !duplicate {
+            cells.entry((x, y)).or_default().push(image);
+        }
+        !duplicate
+    }).collect()
 }
 
+/// The squared length below which an edge is considered degenerate. Projections onto degenerate
+/// edges (e.g. between coincident samples at a stationary point of the mirror) are meaningless, and
+/// dividing by their length would produce NaN.
+const DEGENERATE_LENGTH_2: f64 = 1e-12;
+
 /// Find the distance of a point projected along an edge.
-fn projection_on_edge(edge: &Line<Point2D>, p: Point2D) -> f64 {
-    ((p - edge.from) * (edge.to - edge.from)).sum()
+fn projection_on_edge<S: Scalar>(edge: &Line<Pair<S>>, p: Pair<S>) -> S {
+    (p - edge.from).dot(edge.to - edge.from)
+}
+
+/// Find the coördinates `(u, v)` in the unit square of a point `p` in the quad `[a, b, c, d]`,
+/// where the quad is parameterised bilinearly as
+/// `a + u (b - a) + v (d - a) + u v (a - b + c - d)`. This involves solving a quadratic in `v`,
+/// which degenerates to a linear equation when opposite edges of the quad are parallel. Returns
+/// `None` if the quad is degenerate, or the point does not lie within it.
+fn inverse_bilinear<S: Scalar>(p: Pair<S>, [a, b, c, d]: [Pair<S>; 4]) -> Option<Pair<S>> {
+    // A small tolerance, permitting points on the boundary of the quad.
+    let epsilon = S::of(1e-9);
+    let degenerate = S::of(DEGENERATE_LENGTH_2);
+
+    let (e, f, g, h) = (b - a, d - a, a - b + c - d, p - a);
+    // The coefficients of the quadratic `k2 v² + k1 v + k0 = 0`.
+    let k2 = g.cross(f);
+    let k1 = e.cross(f) + h.cross(g);
+    let k0 = h.cross(e);
+
+    let vs = if Float::abs(k2) < degenerate {
+        if Float::abs(k1) < degenerate {
+            return None;
+        }
+        vec![-k0 / k1]
+    } else {
+        let discriminant = k1.powi(2) - S::of(4.0) * k0 * k2;
+        if discriminant < S::zero() {
+            return None;
+        }
+        let w = discriminant.sqrt();
+        vec![(-k1 - w) / (S::of(2.0) * k2), (-k1 + w) / (S::of(2.0) * k2)]
+    };
+
+    let within = |x: S| x >= -epsilon && x <= S::one() + epsilon;
+    vs.into_iter().map(|v| {
+        // Solve `h = u (e + g v) + v f` for `u`, using whichever component is better conditioned.
+        let denominator = e + g * Pair::diag(v);
+        let u = if Float::abs(denominator.x()) > Float::abs(denominator.y()) {
+            (h.x() - f.x() * v) / denominator.x()
+        } else {
+            (h.y() - f.y() * v) / denominator.y()
+        };
+        Pair::new([u, v])
+    }).find(|uv| within(uv.x()) && within(uv.y()))
+}
+
+/// Interpolate bilinearly between the values `[a, b, c, d]` at the vertices of a quad, at the
+/// coördinates `(u, v)` in the unit square, as in `inverse_bilinear`.
+fn bilinear<T: Scalar>([u, v]: [T; 2], [a, b, c, d]: [Pair<T>; 4]) -> Pair<T> {
+    a.lerp(b, u).lerp(d.lerp(c, u), v)
 }
 
+/// The maximum number of cells in the rasterisation grid: enough for a view of 4096 by 4096 pixels
+/// with cells of a single pixel.
+pub const MAX_GRID_CELLS: usize = 1 << 24;
+
 /// Approximation of a reflection using a rasterisation technique: splitting the view up into a grid
 /// and sampling cells to find those containing points in the reflection. This tends to be accurate,
 /// but can be slow for finer grids.
 pub struct RasterisationApproximator {
-    /// The size of each rasterisation cell in pixels.
+    /// The size of each rasterisation cell in pixels. No reflection is found in views so large
+    /// that the grid would have more than `MAX_GRID_CELLS` cells (see `grid_size`).
     pub cell_size: u16,
+    pub mirror_sampling: MirrorSampling,
 }
 
-impl ReflectionApproximator for RasterisationApproximator {
-    fn approximate_reflection(
+impl RasterisationApproximator {
+    /// The number of cells needed horizontally and vertically to cover the `view` with cells of
+    /// `cell_size` pixels, rounding up if the view size isn't perfectly divisible by the cell size.
+    /// This is calculated in `usize`, so that the dimensions of the largest views can be handled
+    /// without care. Returns `None` if the grid would have more than `MAX_GRID_CELLS` cells, and so
+    /// might not fit comfortably in memory.
+    pub fn grid_size(cell_size: u16, view: &View) -> Option<[usize; 2]> {
+        let cell_size = usize::from(cell_size.max(1));
+        let cells = |pixels: u16| (usize::from(pixels) + cell_size - 1) / cell_size;
+        let [cols, rows] = [cells(view.width), cells(view.height)];
+        if cols * rows > MAX_GRID_CELLS {
+            None
+        } else {
+            Some([cols, rows])
+        }
+    }
+}
+
+impl<S: Scalar> ReflectionApproximator<S> for RasterisationApproximator {
+    fn name(&self) -> &'static str {
+        "rasterisation"
+    }
+
+    fn approximate_reflection_streaming_with_params(
         &self,
-        mirror: &Equation<'_, f64>,
-        figure: &Equation<'_, f64>,
-        sigma_tau: &Equation<'_, (f64, f64)>,
-        interval: &Interval,
+        inputs: ReflectionInputs<'_, S>,
         view: &View,
-    ) -> Vec<(Point2D, Point2D, Point2D)> {
-        // Calculate the number of cells we need horizontally and vertically. Round up if the view
-        // size isn't perfectly divisible by the cell size.
-        let [cols, rows] = [
-            ((view.width + self.cell_size - 1) / self.cell_size) as usize,
-            ((view.height + self.cell_size - 1) / self.cell_size) as usize,
-        ];
-        // Each cell (corresponding to a region) contains mappings from points in that region
-        // to their reflections.
-        let mut grid = vec![vec![]; cols * rows];
-
-        // Populate the mapping grid.
-        for t in interval.clone() {
+        mut progress: Progress<'_>,
+        sink: Sink<'_, S>,
+    ) {
+        let ReflectionInputs { mirror, figure, sigma_tau, t_interval, s_interval } = inputs;
+        // Rather than silently rasterising at a coarser resolution than requested, we find no
+        // reflection if the grid would be too large. `Method::check_threshold` reports this case.
+        let [cols, rows] = match Self::grid_size(self.cell_size, view) {
+            Some(grid_size) => grid_size,
+            None => return,
+        };
+        // The images (and parameters) of the points along the normal at `t`, paired with the
+        // indices of the cells containing the points.
+        let row = |t: f64| {
             let normal = mirror.normal(t);
-            for s in interval.clone() {
+            // The normals at translated parameters, which frequently coincide along a row.
+            let mut normals = BTreeMap::new();
+            s_interval.iter().filter_map(move |s| {
                 let point = (normal.function)(s);
-                if let Some([x, y]) = view.project(point, [cols, rows]) {
+                if !point.is_finite() {
+                    return None;
+                }
+                view.project(point, [cols, rows]).and_then(|[x, y]| {
                     let [scale, translate] = (sigma_tau.function)((s, t)).into_inner();
                     // In some cases, we can use cached computations to calculate the reflections.
                     let image = match (scale == s, translate == t) {
                         (true, true) => point,
                         (false, true) => (normal.function)(scale),
-                        (_, false) => (mirror.normal(translate).function)(scale),
+                        (_, false) => {
+                            let normal = normals
+                                .entry(OrdFloat(translate))
+                                .or_insert_with(|| mirror.normal(translate));
+                            (normal.function)(scale)
+                        }
                     };
-                    grid[x as usize + y as usize * cols].push(image);
+                    if image.is_finite() {
+                        Some((x + y * cols, (image, (t, s))))
+                    } else {
+                        None
+                    }
+                })
+            })
+        };
+
+        // The images of the sampled points, paired with the cells containing the points.
+        let mut images = vec![];
+        let ts = self.mirror_sampling.sample(mirror, t_interval);
+
+        #[cfg(not(feature = "parallel"))]
+        for (i, &t) in ts.iter().enumerate() {
+            images.extend(row(t));
+            if !report_progress(&mut progress, i + 1, ts.len()) {
+                break;
+            }
+        }
+
+        // Each `t` row is independent, so we compute a batch of rows in parallel, reporting
+        // progress between batches.
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+
+            let batch_size = rayon::current_num_threads();
+            'batches: for (b, batch) in ts.chunks(batch_size).enumerate() {
+                let rows: Vec<Vec<_>> = batch.par_iter().map(|&t| row(t).collect()).collect();
+                for (i, row) in rows.into_iter().enumerate() {
+                    images.extend(row);
+                    if !report_progress(&mut progress, b * batch_size + i + 1, ts.len()) {
+                        break 'batches;
+                    }
                 }
             }
         }
 
+        // Populate the mapping grid. Each cell (corresponding to a region) contains mappings from
+        // points in that region to their reflections. Rather than allocating a vector for each
+        // cell, the mappings are stored contiguously, those for cell `i` being in the range
+        // `offsets[i]..offsets[i + 1]` of `grid`. We count the mappings in each cell first, so
+        // that we know where each cell begins, and then fill them in.
+        let mut offsets = vec![0; cols * rows + 1];
+        for &(cell, _) in &images {
+            offsets[cell + 1] += 1;
+        }
+        for cell in 0..cols * rows {
+            offsets[cell + 1] += offsets[cell];
+        }
+        let mut grid = vec![(Pair::zero(), (0.0, 0.0)); images.len()];
+        let mut next = offsets.clone();
+        for (cell, image) in images {
+            grid[next[cell]] = image;
+            next[cell] += 1;
+        }
+
         // Intersect the grid with the figure equation, determining all the points corresponding
         // to reflections of points on the figure.
-        let mut reflection = HashSet::new();
-        for point in figure.sample(&interval) {
+        let mut reflection = BTreeSet::new();
+        for &point in figure.iter().filter(|point| point.is_finite()) {
             if let Some(cell) = view.project(point, [cols, rows]) {
                 reflection.insert(cell);
             }
         }
 
-        reflection.into_iter().flat_map(|[x, y]| {
-            &grid[x as usize + y as usize * cols]
-        }).cloned().map(|p| (p, Point2D::zero(), Point2D::zero())).collect()
+        for [x, y] in reflection {
+            let cell = x + y * cols;
+            for &(p, params) in &grid[offsets[cell]..offsets[cell + 1]] {
+                sink((p, Pair::zero(), Pair::zero()), params);
+            }
+        }
     }
 }
 
-pub struct QuadraticApproximator;
+/// A triple corresponding to a point and its reflection, as well as the point in which it was
+/// reflected.
+#[derive(Clone, Copy)]
+struct Reflection<S> {
+    /// `point` is a point in space (one to be reflected).
+    point: Pair<S>,
+    /// `surface` is the point along the mirror surface in which `point` is reflected.
+    surface: Pair<S>,
+    /// `image` is the reflection of the `point` in the `surface`.
+    image: Pair<S>,
+    /// `params` are the `(t, s)` parameters corresponding to `point`.
+    params: Point2D,
+}
 
-impl ReflectionApproximator for QuadraticApproximator {
-    fn approximate_reflection(
+/// A quad in (t, s) space, carrying its index and the reflections at its vertices.
+type Region<S> = RTreeObjectWithData<
+    Quad<Pair<S>>,
+    (usize, (Reflection<S>, Reflection<S>, Reflection<S>, Reflection<S>)),
+>;
+
+/// Find the reflection of a `point` lying within the `quad`, by interpolating bilinearly between
+/// the reflections at its vertices. Returns `None` if the point does not lie within the quad.
+fn interpolate_within_quad<S: Scalar>(
+    quad: &Quad<Pair<S>>,
+    (a, b, c, d): (Reflection<S>, Reflection<S>, Reflection<S>, Reflection<S>),
+    point: Pair<S>,
+) -> Option<(ReflectionTriple<S>, (f64, f64))> {
+    // Find the coördinates of the point within the quad, and interpolate the reflections
+    // corresponding to the quad vertices bilinearly.
+    let [u, v] = inverse_bilinear(point, quad.points)?.into_inner();
+    let weight = |a, b, c, d| bilinear([u, v], [a, b, c, d]);
+
+    let [t, s] = bilinear(
+        [u.as_f64(), v.as_f64()],
+        [a.params, b.params, c.params, d.params],
+    ).into_inner();
+    Some(((
+        weight(a.image, b.image, c.image, d.image),
+        weight(quad.points[0], quad.points[1], quad.points[2], quad.points[3]),
+        weight(a.surface, b.surface, c.surface, d.surface),
+    ), (t, s)))
+}
+
+pub struct QuadraticApproximator {
+    pub mirror_sampling: MirrorSampling,
+}
+
+impl QuadraticApproximator {
+    /// Build an index of the reflections of points within the region swept out by the normals to
+    /// the mirror, which may be queried repeatedly for the reflections of arbitrary points.
+    pub fn reflection_index<S: Scalar>(
         &self,
-        mirror: &Equation<'_, f64>,
-        figure: &Equation<'_, f64>,
+        mirror: &Equation<'_, f64, Pair<S>>,
         sigma_tau: &Equation<'_, (f64, f64)>,
-        interval: &Interval,
-        _: &View,
-    ) -> Vec<(Point2D, Point2D, Point2D)> {
-        /// A triple corresponding to a point and its reflection, as well as the point in which it
-        /// was reflected.
-        #[derive(Clone, Copy)]
-        struct Reflection {
-            /// `point` is a point in space (one to be reflected).
-            point: Point2D,
-            /// `surface` is the point along the mirror surface in which `point` is reflected.
-            surface: Point2D,
-            /// `image` is the reflection of the `point` in the `surface`.
-            image: Point2D,
-        }
+        t_interval: &Interval,
+        s_interval: &Interval,
+        progress: Progress<'_>,
+    ) -> ReflectionIndex<S> {
+        let regions = self.reflection_regions(mirror, sigma_tau, t_interval, s_interval, progress);
+        ReflectionIndex { regions: Regions::Quads(RTree::bulk_load(regions)) }
+    }
+
+    /// Sample the normals to the mirror, returning the quads between adjacent normals.
+    fn reflection_regions<S: Scalar>(
+        &self,
+        mirror: &Equation<'_, f64, Pair<S>>,
+        sigma_tau: &Equation<'_, (f64, f64)>,
+        t_interval: &Interval,
+        s_interval: &Interval,
+        mut progress: Progress<'_>,
+    ) -> Vec<Region<S>> {
+        // The normals are straight lines, so it suffices to sample `s` at the endpoints of the
+        // supplied `s` interval.
+        let endpoint_interval = Interval::endpoints(s_interval.start, s_interval.end);
 
         // Sample points in (t, s) space.
-        let samples: Vec<_> = interval.clone().map(|t| {
+        let ts = self.mirror_sampling.sample(mirror, t_interval);
+        let mut samples = vec![];
+        for (i, &t) in ts.iter().enumerate() {
             let normal = mirror.normal(t);
             let surface = (normal.function)(0.0);
-            let endpoint_interval = Interval::endpoints(interval.start, interval.end);
 
-            endpoint_interval.filter_map(|s| {
+            samples.push(endpoint_interval.iter().filter_map(|s| {
                 let point = (normal.function)(s);
 
-                if !point.is_nan() {
+                if point.is_finite() {
                     let [scale, translate] = (sigma_tau.function)((s, t)).into_inner();
                     // In some cases, we can use cached computations to calculate the reflections.
                     let image = match (scale == s, translate == t) {
@@ -124,21 +540,38 @@ impl ReflectionApproximator for QuadraticApproximator {
                         (false, true) => (normal.function)(scale),
                         (_, false) => (mirror.normal(translate).function)(scale),
                     };
-                    if !image.is_nan() {
+                    if image.is_finite() {
                         // The point `point` is reflected in the mirror at the point `surface`
                         // to the point `image`.
-                        return Some(Reflection { point, surface, image });
+                        let params = Point2D::new([t, s]);
+                        return Some(Reflection { point, surface, image, params });
                     }
                 }
 
                 None
-            }).collect::<Vec<_>>()
-        }).collect();
+            }).collect::<Vec<_>>());
+
+            if !report_progress(&mut progress, i + 1, ts.len()) {
+                break;
+            }
+        }
 
         // A collection of quads with (point, image) data at each point, used for
         // image interpolation.
         let mut reflection_regions = vec![];
 
+        // If the interval is periodic, the last normal is adjacent to the first, so there is an
+        // additional row of quads across the seam. The parameters of the first normal are shifted
+        // by the period, so that they are interpolated across the seam, rather than the interval.
+        if t_interval.periodic && samples.len() == ts.len() && samples.len() > 2 {
+            let first = samples[0].iter().map(|&reflection| {
+                let [t, s] = reflection.params.into_inner();
+                let params = Point2D::new([t + t_interval.period(), s]);
+                Reflection { params, ..reflection }
+            }).collect();
+            samples.push(first);
+        }
+
         // Populate `reflection_regions`.
         for t_pair in samples.windows(2).into_iter() {
             // This pattern match is guaranteed, but unfortuantely, `windows` doesn't contain
@@ -161,13 +594,44 @@ impl ReflectionApproximator for QuadraticApproximator {
             }
         }
 
+        reflection_regions
+    }
+}
+
+impl<S: Scalar> ReflectionApproximator<S> for QuadraticApproximator {
+    fn name(&self) -> &'static str {
+        "quadratic"
+    }
+
+    fn approximate_reflection_streaming_with_params(
+        &self,
+        inputs: ReflectionInputs<'_, S>,
+        _: &View,
+        progress: Progress<'_>,
+        sink: Sink<'_, S>,
+    ) {
+        self.approximate_reflection_streaming_without_view(inputs, progress, sink)
+    }
+}
+
+impl<S: Scalar> ViewIndependentApproximator<S> for QuadraticApproximator {
+    fn approximate_reflection_streaming_without_view(
+        &self,
+        inputs: ReflectionInputs<'_, S>,
+        progress: Progress<'_>,
+        sink: Sink<'_, S>,
+    ) {
+        let ReflectionInputs { mirror, figure, sigma_tau, t_interval, s_interval } = inputs;
+        let reflection_regions =
+            self.reflection_regions(mirror, sigma_tau, t_interval, s_interval, progress);
+
         // Store the regions spatially, so we can lookup points within those regions.
         let rtree = RTree::bulk_load(reflection_regions.clone());
 
-        let mut reflection = HashMap::new();
+        let mut reflection = BTreeMap::new();
 
         // Sample points along the figure and find all quads within which they lie.
-        for point in figure.sample(&interval).into_iter().filter(|point| !point.is_nan()) {
+        for &point in figure.iter().filter(|point| point.is_finite()) {
             rtree.locate_all_at_point(&point).for_each(|quad| {
                 reflection.entry((quad.1).0).or_insert(vec![]).push(point);
             });
@@ -175,114 +639,487 @@ impl ReflectionApproximator for QuadraticApproximator {
 
         reflection.into_iter()
             .map(|(index, points)| (reflection_regions[index].clone(), points))
-            .flat_map(|(RTreeObjectWithData(quad, (_, (a, b, c, d))), points)| {
-                points.into_iter().map(|point| {
-                    // Interpolate the possible reflections corresponding to the quad vertices in
-                    // comparison to the point.
-                    let len_a = quad.edges[0].length_2();
-                    let len_b = quad.edges[2].length_2();
-                    let proj = Pair::new([
-                        projection_on_edge(&quad.edges[0], point) / len_a,
-                        1.0 - projection_on_edge(&quad.edges[2], point) / len_b,
-                    ]);
-                    let dis = Point2D::new([
-                        quad.edges[0].distance_2(&point),
-                        quad.edges[2].distance_2(&point),
-                    ]);
-                    let factor = Point2D::one() - dis / Point2D::diag(dis.sum());
-                    let weight = |w, x, y, z| {
-                        let [base, end] = [Pair::new([w, z]), Pair::new([x, y])];
-                        ((base + (end - base) * proj.map(Pair::diag)) * factor.map(Pair::diag))
-                            .sum()
-                    };
-
-                    (
-                        weight(a.image, b.image, c.image, d.image),
-                        weight(quad.points[0], quad.points[1], quad.points[2], quad.points[3]),
-                        weight(a.surface, b.surface, c.surface, d.surface),
-                    )
+            .flat_map(|(RTreeObjectWithData(quad, (_, vertices)), points)| {
+                points.into_iter().filter_map(|point| {
+                    interpolate_within_quad(&quad, vertices, point)
                 }).collect::<Vec<_>>()
             })
-            .collect()
+            .for_each(|(triple, params)| sink(triple, params));
+    }
+}
+
+/// A segment of a line in (t, s) space, carrying its index, the images of its endpoints, and the
+/// parameters `(t, s_l, s_r)` of its endpoints.
+type Segment<S> = RTreeObjectWithData<Line<Pair<S>>, (usize, (Pair<S>, Pair<S>), (f64, f64, f64))>;
+
+/// Find the images of the figure points whose squared distance from the segments is at most
+/// `threshold`, by interpolating linearly between the images of the endpoints of the segments, and
+/// pass them to `sink`.
+fn interpolate_along_segments<S: Scalar>(
+    reflection_lines: Vec<Segment<S>>,
+    figure: &[Pair<S>],
+    threshold: S,
+    sink: Sink<'_, S>,
+) {
+    let rtree = RTree::bulk_load(reflection_lines.clone());
+    let mut reflection = BTreeMap::new();
+
+    // Sample points along the figure, finding the closest line segment along the mirror and
+    // interpolating the reflection image.
+    for &point in figure.iter().filter(|point| point.is_finite()) {
+        rtree.locate_within_distance(point, threshold).for_each(|line| {
+            reflection.entry((line.1).0).or_insert(vec![]).push(point);
+        });
+    }
+
+    let zero = Pair::zero();
+    reflection.into_iter()
+        .map(|(index, points)| (reflection_lines[index].clone(), points))
+        .flat_map(|(RTreeObjectWithData(fig, (_, (base, end), (t, s_l, s_r))), points)| {
+            points.into_iter().filter_map(|point| {
+                // Find the closest point on the line `fig` to the point `p` as a parameter from
+                // 0 to 1.
+                let s = projection_on_edge(&fig, point);
+                let len = fig.length_2();
+                if len >= S::of(DEGENERATE_LENGTH_2) && s >= S::zero() && s <= len {
+                    let factor = s / len;
+                    Some((
+                        (base.lerp(end, factor), zero, zero),
+                        (t, s_l + (s_r - s_l) * factor.as_f64()),
+                    ))
+                } else {
+                    None
+                }
+            }).collect::<Vec<_>>()
+        })
+        .for_each(|(triple, params)| sink(triple, params));
+}
+
+/// If the figure is a straight line, return the endpoints of the segment it spans. The figure is
+/// taken to be straight if every sample is finite and lies on the line through the first and last
+/// samples, up to rounding error. Samples may double back along the line, as the figure is assumed
+/// to be continuous, so the segment is bounded by the extreme samples, rather than the endpoints.
+fn straight_segment<S: Scalar>(figure: &[Pair<S>]) -> Option<(Pair<S>, Pair<S>)> {
+    let (first, last) = (*figure.first()?, *figure.last()?);
+    let direction = last - first;
+    let length_2 = direction.dot(direction);
+    if !first.is_finite() || length_2 <= S::zero() || length_2.is_nan() {
+        return None;
+    }
+
+    // The extent of the samples along the line, as multiples of `direction` from `first`.
+    let (mut min, mut max) = (S::zero(), S::one());
+    for &point in figure {
+        let along = (point - first).dot(direction) / length_2;
+        let projection = first + direction * Pair::diag(along);
+        if !point.is_finite() || !point.approx_eq(projection) {
+            return None;
+        }
+        min = min.min(along);
+        max = max.max(along);
+    }
+    let along = |x: S| first + direction * Pair::diag(x);
+    Some((along(min), along(max)))
+}
+
+/// Find the images of the points at which the straight figure from `from` to `to` crosses the
+/// segments, by interpolating linearly between the images of the endpoints of the segments, and
+/// pass them to `sink`. Segments parallel to the figure are ignored.
+fn intersect_segments<S: Scalar>(
+    reflection_lines: Vec<Segment<S>>,
+    (from, to): (Pair<S>, Pair<S>),
+    sink: Sink<'_, S>,
+) {
+    let zero = Pair::zero();
+    let direction = to - from;
+    let within = |x: S| x >= S::zero() && x <= S::one();
+    for RTreeObjectWithData(fig, (_, (base, end), (t, s_l, s_r))) in reflection_lines {
+        // Solve `fig.from + along * factor = from + direction * position` for the parameters of
+        // the intersection along each segment.
+        let along = fig.to - fig.from;
+        let denominator = along.cross(direction);
+        if fig.length_2() < S::of(DEGENERATE_LENGTH_2) || denominator == S::zero() {
+            continue;
+        }
+        let offset = from - fig.from;
+        let factor = offset.cross(direction) / denominator;
+        let position = offset.cross(along) / denominator;
+        if within(factor) && within(position) {
+            sink((base.lerp(end, factor), zero, zero), (t, s_l + (s_r - s_l) * factor.as_f64()));
+        }
     }
 }
 
 pub struct LinearApproximator {
+    /// The squared distance within which a figure point is matched to a normal to the mirror.
     pub threshold: f64,
+    pub mirror_sampling: MirrorSampling,
+    /// If set, figures that are straight lines are intersected with the normals to the mirror
+    /// exactly, rather than being matched point-by-point. This finds one reflection per normal
+    /// crossed by the figure, regardless of `threshold`, and is much cheaper for dense figures.
+    pub intersect_straight_figures: bool,
 }
 
-impl ReflectionApproximator for LinearApproximator {
-    fn approximate_reflection(
+impl LinearApproximator {
+    /// Build an index of the reflections of points along the normals to the mirror, which may be
+    /// queried repeatedly for the reflections of arbitrary points.
+    pub fn reflection_index<S: Scalar>(
         &self,
-        mirror: &Equation<'_, f64>,
-        figure: &Equation<'_, f64>,
+        mirror: &Equation<'_, f64, Pair<S>>,
         sigma_tau: &Equation<'_, (f64, f64)>,
-        interval: &Interval,
-        _view: &View,
-    ) -> Vec<(Point2D, Point2D, Point2D)> {
+        t_interval: &Interval,
+        s_interval: &Interval,
+        progress: Progress<'_>,
+    ) -> ReflectionIndex<S> {
+        let lines = self.reflection_lines(mirror, sigma_tau, t_interval, s_interval, progress);
+        ReflectionIndex { regions: Regions::Segments(RTree::bulk_load(lines)) }
+    }
+
+    /// Sample the normals to the mirror, returning the segments along each normal.
+    fn reflection_lines<S: Scalar>(
+        &self,
+        mirror: &Equation<'_, f64, Pair<S>>,
+        sigma_tau: &Equation<'_, (f64, f64)>,
+        t_interval: &Interval,
+        s_interval: &Interval,
+        mut progress: Progress<'_>,
+    ) -> Vec<Segment<S>> {
         // A collection of lines with (point, image) data at each point, used for
         // image interpolation.
         let mut reflection_lines = vec![];
 
+        // The normals are straight lines, so it suffices to sample `s` at the endpoints of the
+        // supplied `s` interval.
+        let endpoint_interval = Interval::endpoints(s_interval.start, s_interval.end);
+
         // Sample points along the mirror, mapping points (t, s) to their images.
-        for t in interval.clone() {
+        let ts = self.mirror_sampling.sample(mirror, t_interval);
+        for (i, &t) in ts.iter().enumerate() {
             let normal = mirror.normal(t);
-            let endpoint_interval = Interval::endpoints(interval.start, interval.end);
 
-            let samples: Vec<_> = endpoint_interval.map(|s| {
+            let samples: Vec<_> = endpoint_interval.iter().map(|s| {
                 let point = (normal.function)(s);
                 let [scale, translate] = (sigma_tau.function)((s, t)).into_inner();
-                let image = match (scale == s, translate == 0.0) {
+                let image = match (scale == s, translate == t) {
                     (true, true) => point,
                     (false, true) => (normal.function)(scale),
                     (_, false) => (mirror.normal(translate).function)(scale),
                 };
-                (point, image)
-            }).collect();
+                (point, image, s)
+            }).filter(|(point, image, _)| point.is_finite() && image.is_finite()).collect();
 
             for window in samples.windows(2) {
                 // Guaranteed to pattern match successfully.
-                if let &[(point_l, image_l), (point_r, image_r)] = window {
+                if let &[(point_l, image_l, s_l), (point_r, image_r, s_r)] = window {
                     let index = reflection_lines.len();
                     reflection_lines.push(RTreeObjectWithData(
                         Line::new(point_l, point_r),
-                        (index, (image_l, image_r)),
+                        (index, (image_l, image_r), (t, s_l, s_r)),
                     ));
                 }
             }
+
+            if !report_progress(&mut progress, i + 1, ts.len()) {
+                break;
+            }
         }
 
-        let rtree = RTree::bulk_load(reflection_lines.clone());
-        let mut reflection = HashMap::new();
+        reflection_lines
+    }
+}
+
+impl<S: Scalar> ReflectionApproximator<S> for LinearApproximator {
+    fn name(&self) -> &'static str {
+        "linear"
+    }
 
-        let threshold = self.threshold.sqrt();
+    fn approximate_reflection_streaming_with_params(
+        &self,
+        inputs: ReflectionInputs<'_, S>,
+        _: &View,
+        progress: Progress<'_>,
+        sink: Sink<'_, S>,
+    ) {
+        self.approximate_reflection_streaming_without_view(inputs, progress, sink)
+    }
+}
 
-        // Sample points along the figure, finding the closest line segment along the mirror and
-        // interpolating the reflection image.
-        for point in figure.sample(&interval) {
-            rtree.locate_within_distance(point, self.threshold).for_each(|line| {
-                if line.distance_2(&point) <= threshold {
-                    reflection.entry((line.1).0).or_insert(vec![]).push(point);
+impl<S: Scalar> ViewIndependentApproximator<S> for LinearApproximator {
+    fn approximate_reflection_streaming_without_view(
+        &self,
+        inputs: ReflectionInputs<'_, S>,
+        progress: Progress<'_>,
+        sink: Sink<'_, S>,
+    ) {
+        let ReflectionInputs { mirror, figure, sigma_tau, t_interval, s_interval } = inputs;
+        let reflection_lines =
+            self.reflection_lines(mirror, sigma_tau, t_interval, s_interval, progress);
+        let segment = if self.intersect_straight_figures { straight_segment(figure) } else { None };
+        match segment {
+            Some(segment) => intersect_segments(reflection_lines, segment, sink),
+            None => {
+                interpolate_along_segments(reflection_lines, figure, S::of(self.threshold), sink)
+            }
+        }
+    }
+}
+
+/// Exact reflection in a flat mirror: one that is an affine function of `t`, such as `(t, 0)`. The
+/// normals to such a mirror are parallel, so the `(t, s)` parameters of each figure point may be
+/// found in closed form, without sampling the mirror. If the mirror is not affine over the `t`
+/// interval, the `fallback` approximator is used instead.
+pub struct FlatMirrorApproximator<A> {
+    pub fallback: A,
+}
+
+impl<A> FlatMirrorApproximator<A> {
+    /// Reflect the figure exactly in the mirror `origin + t * direction`.
+    fn reflect<S: Scalar>(
+        (origin, direction): (Pair<S>, Pair<S>),
+        inputs: ReflectionInputs<'_, S>,
+        mut progress: Progress<'_>,
+        sink: Sink<'_, S>,
+    ) {
+        let ReflectionInputs { figure, sigma_tau, t_interval, s_interval, .. } = inputs;
+        // The unit normal, oriented as in `Equation::normal`.
+        let speed_2 = direction.dot(direction);
+        let normal = Pair::new([-direction.y(), direction.x()]) / Pair::diag(speed_2.sqrt());
+        let along = |t: f64, s: f64| {
+            origin + direction * Pair::diag(S::of(t)) + normal * Pair::diag(S::of(s))
+        };
+
+        for &point in figure.iter().filter(|point| point.is_finite()) {
+            let offset = point - origin;
+            let (t, s) = ((offset.dot(direction) / speed_2).as_f64(), offset.dot(normal).as_f64());
+            if !t_interval.contains(t) || !s_interval.contains(s) {
+                continue;
+            }
+            let [scale, translate] = (sigma_tau.function)((s, t)).into_inner();
+            let image = along(translate, scale);
+            if image.is_finite() {
+                sink((image, point, along(t, 0.0)), (t, s));
+            }
+        }
+
+        report_progress(&mut progress, 1, 1);
+    }
+}
+
+impl<S: Scalar, A: ReflectionApproximator<S>> ReflectionApproximator<S>
+    for FlatMirrorApproximator<A>
+{
+    fn name(&self) -> &'static str {
+        "flat"
+    }
+
+    fn approximate_reflection_streaming_with_params(
+        &self,
+        inputs: ReflectionInputs<'_, S>,
+        view: &View,
+        progress: Progress<'_>,
+        sink: Sink<'_, S>,
+    ) {
+        match inputs.mirror.affine(inputs.t_interval) {
+            Some(line) => Self::reflect(line, inputs, progress, sink),
+            None => {
+                let fallback = &self.fallback;
+                fallback.approximate_reflection_streaming_with_params(inputs, view, progress, sink)
+            }
+        }
+    }
+}
+
+impl<S: Scalar, A: ViewIndependentApproximator<S>> ViewIndependentApproximator<S>
+    for FlatMirrorApproximator<A>
+{
+    fn approximate_reflection_streaming_without_view(
+        &self,
+        inputs: ReflectionInputs<'_, S>,
+        progress: Progress<'_>,
+        sink: Sink<'_, S>,
+    ) {
+        match inputs.mirror.affine(inputs.t_interval) {
+            Some(line) => Self::reflect(line, inputs, progress, sink),
+            None => {
+                let fallback = &self.fallback;
+                fallback.approximate_reflection_streaming_without_view(inputs, progress, sink)
+            }
+        }
+    }
+}
+
+/// An index of the reflections of the points in the region covered by an approximation, which
+/// finds the reflection of an arbitrary point (e.g. one the user is hovering over) without
+/// resampling the mirror for each query.
+pub struct ReflectionIndex<S: Scalar = f64> {
+    regions: Regions<S>,
+}
+
+/// The regions stored by a `ReflectionIndex`, depending on the approximator that built it.
+enum Regions<S: Scalar> {
+    Segments(RTree<Segment<S>>),
+    Quads(RTree<Region<S>>),
+}
+
+impl<S: Scalar> ReflectionIndex<S> {
+    /// The reflection of the point `p` in the region of the index nearest to it, together with the
+    /// `(t, s)` parameters of the point. Points outside the regions of the index take the
+    /// reflection of the nearest point on the boundary of the region (approximately, for quads).
+    /// Returns `None` only if the index is empty.
+    pub fn nearest(&self, p: Pair<S>) -> Option<(Pair<S>, f64, f64)> {
+        match &self.regions {
+            Regions::Segments(rtree) => {
+                let RTreeObjectWithData(line, (_, (base, end), (t, s_l, s_r))) =
+                    rtree.nearest_neighbor(&p)?;
+                // The position of the closest point on the segment, as a parameter from 0 to 1.
+                let len = line.length_2();
+                let factor = if len >= S::of(DEGENERATE_LENGTH_2) {
+                    (projection_on_edge(line, p) / len).max(S::zero()).min(S::one())
+                } else {
+                    S::zero()
+                };
+                let image = base.lerp(*end, factor);
+                Some((image, *t, s_l + (s_r - s_l) * factor.as_f64()))
+            }
+            Regions::Quads(rtree) => {
+                let RTreeObjectWithData(quad, (_, vertices)) = rtree.nearest_neighbor(&p)?;
+                if let Some(((image, _, _), (t, s))) = interpolate_within_quad(quad, *vertices, p) {
+                    return Some((image, t, s));
                 }
-            });
+                // The point lies outside the quad, so we take the reflection of its nearest
+                // vertex.
+                let (a, b, c, d) = *vertices;
+                let nearest = [a, b, c, d].iter()
+                    .min_by_key(|r| OrdFloat((r.point - p).dot(r.point - p).as_f64()))
+                    .cloned()?;
+                let [t, s] = nearest.params.into_inner();
+                Some((nearest.image, t, s))
+            }
         }
+    }
+}
 
-        let zero = Point2D::zero();
-        reflection.into_iter()
-            .map(|(index, points)| (reflection_lines[index].clone(), points))
-            .flat_map(|(RTreeObjectWithData(fig, (_, (base, end))), points)| {
-                points.into_iter().filter_map(|point| {
-                    // Find the closest point on the line `fig` to the point `p` as a parameter from
-                    // 0 to 1.
-                    let s = projection_on_edge(&fig, point);
-                    let len = fig.length_2();
-                    if s >= 0.0 && s <= len {
-                        Some((base + (end - base) * Point2D::diag(s / len), zero, zero))
-                    } else {
-                        None
-                    }
-                }).collect::<Vec<_>>()
-            })
-            .collect()
+/// Reflect the point `p` across the tangent line of the mirror at `t`: that is, the affine
+/// reflection of `p` in the mirror at a single point.
+pub fn reflect_point<S: Scalar>(
+    mirror: &Equation<'_, f64, Pair<S>>,
+    t: f64,
+    p: Pair<S>,
+) -> Pair<S> {
+    let normal = mirror.normal(t);
+    let base = (normal.function)(0.0);
+    // The normal equation is parameterised by arc length, so this is the unit normal.
+    let unit = (normal.function)(1.0) - base;
+    p - unit * Pair::diag(S::of(2.0) * (p - base).dot(unit))
+}
+
+/// Reflect the point `p` across the (infinite) line through `a` and `b`, e.g. a segment between
+/// two samples of a mirror. This is exact, so serves as a reference for the approximators. The
+/// result is NaN if `a` and `b` coincide.
+pub fn reflect_across_segment<S: Scalar>(a: Pair<S>, b: Pair<S>, p: Pair<S>) -> Pair<S> {
+    let direction = b - a;
+    // The projection of `p` onto the line.
+    let foot = a.lerp(b, (p - a).dot(direction) / direction.dot(direction));
+    foot + foot - p
+}
+
+/// Refract a ray travelling in `direction` at an interface with the given `normal` (which may face
+/// either way), according to Snell's law, where `index_ratio` is the ratio `n₁ / n₂` of the
+/// refractive indices on the incident and refracted sides. Returns the unit direction of the
+/// refracted ray, or `None` in the case of total internal reflection.
+pub fn refract<S: Scalar>(direction: Pair<S>, normal: Pair<S>, index_ratio: S) -> Option<Pair<S>> {
+    let (incident, mut normal) = (direction.normalise(), normal.normalise());
+    let mut cos_i = -incident.dot(normal);
+    // Orient the normal against the incident ray.
+    if cos_i < S::zero() {
+        normal = normal * Pair::diag(-S::one());
+        cos_i = -cos_i;
+    }
+
+    let k = S::one() - index_ratio.powi(2) * (S::one() - cos_i.powi(2));
+    if k < S::zero() {
+        None
+    } else {
+        let factor = index_ratio * cos_i - k.sqrt();
+        Some(incident * Pair::diag(index_ratio) + normal * Pair::diag(factor))
+    }
+}
+
+/// Approximation of a refraction, treating the mirror as an interface between two media. Rays
+/// travelling in a fixed `direction` are bent according to Snell's law at each point along the
+/// mirror. A point at distance `s` along a refracted ray appears at distance `s` along the
+/// unrefracted ray, which is where it is seen by an observer looking back along the incident rays.
+/// The figure points are matched to the refracted rays as in `LinearApproximator`. Only the part of
+/// the `s` interval with `s ≥ 0` is used, as the refracted rays begin at the mirror. `sigma_tau` is
+/// not used.
+pub struct RefractionApproximator {
+    /// The ratio `n₁ / n₂` of the refractive indices on the incident and refracted sides.
+    pub index_ratio: f64,
+    /// The direction of the incident rays.
+    pub direction: Point2D,
+    /// The squared distance within which a figure point is matched to a refracted ray.
+    pub threshold: f64,
+    pub mirror_sampling: MirrorSampling,
+}
+
+impl<S: Scalar> ReflectionApproximator<S> for RefractionApproximator {
+    fn name(&self) -> &'static str {
+        "refraction"
+    }
+
+    fn approximate_reflection_streaming_with_params(
+        &self,
+        inputs: ReflectionInputs<'_, S>,
+        _: &View,
+        progress: Progress<'_>,
+        sink: Sink<'_, S>,
+    ) {
+        self.approximate_reflection_streaming_without_view(inputs, progress, sink)
+    }
+}
+
+impl<S: Scalar> ViewIndependentApproximator<S> for RefractionApproximator {
+    fn approximate_reflection_streaming_without_view(
+        &self,
+        inputs: ReflectionInputs<'_, S>,
+        mut progress: Progress<'_>,
+        sink: Sink<'_, S>,
+    ) {
+        let ReflectionInputs { mirror, figure, t_interval, s_interval, .. } = inputs;
+        let mut refraction_lines = vec![];
+
+        let incident = self.direction.map(S::of).normalise();
+        // Negative values of `s` lie on the incident side of the interface, where there is no
+        // refracted ray, so the window is restricted to `s ≥ 0`. `max` also replaces a NaN endpoint
+        // by `0`, so the comparison below cannot be with NaN.
+        let (s_l, s_r) = (s_interval.start.max(0.0), s_interval.end.max(0.0));
+        if s_l >= s_r {
+            return;
+        }
+
+        // Sample points along the mirror, tracing the refracted ray at each point.
+        let ts = self.mirror_sampling.sample(mirror, t_interval);
+        for (i, &t) in ts.iter().enumerate() {
+            let normal = mirror.normal(t);
+            let surface = (normal.function)(0.0);
+            let index_ratio = S::of(self.index_ratio);
+            let refracted = refract(incident, (normal.function)(1.0) - surface, index_ratio);
+
+            // The normal is undefined at stationary points of the mirror.
+            if let Some(refracted) = refracted.filter(|refracted| refracted.is_finite()) {
+                let along = |direction, s| surface + direction * Pair::diag(S::of(s));
+                let index = refraction_lines.len();
+                refraction_lines.push(RTreeObjectWithData(
+                    Line::new(along(refracted, s_l), along(refracted, s_r)),
+                    (index, (along(incident, s_l), along(incident, s_r)), (t, s_l, s_r)),
+                ));
+            }
+
+            if !report_progress(&mut progress, i + 1, ts.len()) {
+                break;
+            }
+        }
+
+        interpolate_along_segments(refraction_lines, figure, S::of(self.threshold), sink)
     }
 }
@@ -5,7 +5,7 @@ use std::fmt::Debug;
 use std::ops::RangeInclusive;
 
 use crate::approximation::OrdFloat;
-use crate::spatial::Point2D;
+use crate::spatial::{Point2D, Point3D};
 
 /// A simple key-value pair. Traits are implemented solely on the key.
 #[derive(Clone, Copy)]
@@ -82,6 +82,14 @@ impl Metric for Point2D {
     }
 }
 
+impl Metric for Point3D {
+    type Output = OrdFloat;
+
+    fn distance(&self, other: &Self) -> Self::Output {
+        OrdFloat((*self - *other).map(|x| x.powf(2.0)).sum())
+    }
+}
+
 /// Sample a range according to a metric, bisecting subranges of the greatest distance to produce
 /// evenly-spaced samples.
 pub fn adaptive_sample<K: Clone + Metric, V: Clone, F: Fn(f64) -> KeyValue<K, V>>(
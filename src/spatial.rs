@@ -2,11 +2,41 @@ use std::cmp::Ordering;
 use std::fmt::Debug;
 use std::ops::{Add, Div, Mul, Sub};
 
-use num_traits::{sign::Signed, bounds::Bounded};
+use num_traits::{sign::Signed, bounds::Bounded, Float};
 use rstar::{AABB, Envelope, Point, PointDistance, primitives::Line, RTreeObject};
 
 use crate::approximation::OrdFloat;
 
+/// The type of the coördinates of points. This is `f64` by default, but `f32` may be used to halve
+/// the memory taken by points, at the cost of precision.
+pub trait Scalar: Float + Signed + Bounded + Debug + Send + Sync + 'static {
+    /// Convert an `f64` to the scalar type, rounding if necessary.
+    fn of(x: f64) -> Self;
+
+    /// Convert the scalar to an `f64`.
+    fn as_f64(self) -> f64;
+}
+
+impl Scalar for f32 {
+    fn of(x: f64) -> Self {
+        x as f32
+    }
+
+    fn as_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Scalar for f64 {
+    fn of(x: f64) -> Self {
+        x
+    }
+
+    fn as_f64(self) -> f64 {
+        self
+    }
+}
+
 /// A cartesian point with some helper methods.
 #[derive(Clone, Copy, Debug, PartialEq)]
 #[derive(Serialize, Deserialize)]
@@ -40,12 +70,61 @@ impl<T: Copy> Pair<T> {
     }
 }
 
-impl Pair<f64> {
-    pub fn normalise(&self) -> Pair<f64> {
+impl<T: Float> Pair<T> {
+    pub fn normalise(&self) -> Pair<T> {
         let length_2 = self.0[0] * self.0[0] + self.0[1] * self.0[1];
         let m = length_2.sqrt();
         Pair([self.0[0] / m, self.0[1] / m])
     }
+
+    pub fn zero() -> Self {
+        Self([T::zero(), T::zero()])
+    }
+
+    pub fn one() -> Self {
+        Self([T::one(), T::one()])
+    }
+
+    pub fn is_nan(&self) -> bool {
+        self.x().is_nan() || self.y().is_nan()
+    }
+
+    /// Whether both components are finite: neither infinite nor NaN.
+    pub fn is_finite(&self) -> bool {
+        self.x().is_finite() && self.y().is_finite()
+    }
+
+    /// Interpolate linearly between `self` (at `t = 0`) and `other` (at `t = 1`). Values of `t`
+    /// outside `[0, 1]` extrapolate along the line through the two points.
+    pub fn lerp(self, other: Pair<T>, t: T) -> Pair<T> {
+        self + (other - self) * Pair::diag(t)
+    }
+
+    /// The element-wise minimum of two pairs. As with `Float::min`, NaN components are ignored.
+    pub fn min(self, other: Pair<T>) -> Pair<T> {
+        Pair([self.x().min(other.x()), self.y().min(other.y())])
+    }
+
+    /// The element-wise maximum of two pairs. As with `Float::max`, NaN components are ignored.
+    pub fn max(self, other: Pair<T>) -> Pair<T> {
+        Pair([self.x().max(other.x()), self.y().max(other.y())])
+    }
+
+    /// Whether `self` is equal to `expected` up to the rounding error accumulated by computing it,
+    /// relative to the magnitude of its coördinates. This is false if `self` has a NaN component.
+    pub fn approx_eq(self, expected: Pair<T>) -> bool {
+        let tolerance = T::epsilon().sqrt();
+        let [x, y] = (self - expected).into_inner();
+        let scale = T::one().max(self.x().abs()).max(self.y().abs());
+        !self.is_nan() && x.abs().max(y.abs()) <= tolerance * scale
+    }
+}
+
+impl<T: Copy + Signed> Pair<T> {
+    /// The element-wise absolute value.
+    pub fn abs(self) -> Pair<T> {
+        self.map(|x| x.abs())
+    }
 }
 
 impl<T: Copy + Debug + PartialOrd + Signed + Bounded> Point for Pair<T> {
@@ -88,6 +167,20 @@ impl<T: Add + Copy> Pair<T> {
     }
 }
 
+impl<T: Copy + Add<Output = T> + Sub<Output = T> + Mul<Output = T>> Pair<T> {
+    /// The dot product of two vectors.
+    pub fn dot(self, other: Pair<T>) -> T {
+        self.x() * other.x() + self.y() * other.y()
+    }
+
+    /// The two-dimensional cross product: the z-component of the three-dimensional cross product,
+    /// or equivalently the signed area of the parallelogram spanned by the two vectors. It is
+    /// positive if `other` is anticlockwise from `self`.
+    pub fn cross(self, other: Pair<T>) -> T {
+        self.x() * other.y() - self.y() * other.x()
+    }
+}
+
 impl<T: Copy + Sub> Sub for Pair<T> {
     type Output = Pair<<T as Sub>::Output>;
 
@@ -114,22 +207,155 @@ impl<T: Copy + Div> Div for Pair<T> {
 
 pub type Point2D = Pair<f64>;
 
-impl Point2D {
+impl From<Point2D> for [f64; 2] {
+    fn from(p: Point2D) -> [f64; 2] {
+        p.0
+    }
+}
+
+impl<T> From<(T, T)> for Pair<T> {
+    fn from((x, y): (T, T)) -> Pair<T> {
+        Pair([x, y])
+    }
+}
+
+impl<T: Copy> From<Pair<T>> for (T, T) {
+    fn from(p: Pair<T>) -> (T, T) {
+        (p.x(), p.y())
+    }
+}
+
+/// A point in three-dimensional space: the three-dimensional analogue of `Pair`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Serialize, Deserialize)]
+pub struct Triple<T>([T; 3]);
+
+impl<T: Copy> Triple<T> {
+    pub fn new(p: [T; 3]) -> Self {
+        Self(p)
+    }
+
+    pub fn diag(d: T) -> Self {
+        Self([d; 3])
+    }
+
+    pub fn into_inner(self) -> [T; 3] {
+        self.0
+    }
+
+    #[inline]
+    pub fn x(&self) -> T {
+        self.0[0]
+    }
+
+    #[inline]
+    pub fn y(&self) -> T {
+        self.0[1]
+    }
+
+    #[inline]
+    pub fn z(&self) -> T {
+        self.0[2]
+    }
+
+    pub fn map<S>(self, f: impl Fn(T) -> S) -> Triple<S> {
+        Triple([f(self.x()), f(self.y()), f(self.z())])
+    }
+}
+
+impl Triple<f64> {
+    pub fn normalise(&self) -> Triple<f64> {
+        let m = (*self * *self).sum().sqrt();
+        self.map(|x| x / m)
+    }
+}
+
+impl<T: Copy + Debug + PartialOrd + Signed + Bounded> Point for Triple<T> {
+    type Scalar = T;
+    const DIMENSIONS: usize = 3;
+
+    fn generate(generator: impl Fn(usize) -> Self::Scalar) -> Self {
+        Triple([generator(0), generator(1), generator(2)])
+    }
+
+    fn nth(&self, index: usize) -> Self::Scalar {
+        self.0[index]
+    }
+
+    fn nth_mut(&mut self, index: usize) -> &mut Self::Scalar {
+        &mut self.0[index]
+    }
+}
+
+impl<T: Copy + PartialOrd> PartialOrd for Triple<T> {
+    fn partial_cmp(&self, other: &Triple<T>) -> Option<Ordering> {
+        match (
+            self.x().partial_cmp(&other.x()),
+            self.y().partial_cmp(&other.y()),
+            self.z().partial_cmp(&other.z()),
+        ) {
+            (Some(x), Some(y), Some(z)) if x == y && y == z => Some(x),
+            _ => None,
+        }
+    }
+}
+
+impl<T: Add + Copy> Add for Triple<T> {
+    type Output = Triple<<T as Add>::Output>;
+
+    fn add(self, other: Triple<T>) -> Self::Output {
+        Triple([self.x() + other.x(), self.y() + other.y(), self.z() + other.z()])
+    }
+}
+
+impl<T: Add<Output = T> + Copy> Triple<T> {
+    pub fn sum(self) -> T {
+        self.x() + self.y() + self.z()
+    }
+}
+
+impl<T: Copy + Sub> Sub for Triple<T> {
+    type Output = Triple<<T as Sub>::Output>;
+
+    fn sub(self, other: Triple<T>) -> Self::Output {
+        Triple([self.x() - other.x(), self.y() - other.y(), self.z() - other.z()])
+    }
+}
+
+impl<T: Copy + Mul> Mul for Triple<T> {
+    type Output = Triple<<T as Mul>::Output>;
+
+    fn mul(self, other: Triple<T>) -> Self::Output {
+        Triple([self.x() * other.x(), self.y() * other.y(), self.z() * other.z()])
+    }
+}
+
+impl<T: Copy + Div> Div for Triple<T> {
+    type Output = Triple<<T as Div>::Output>;
+
+    fn div(self, other: Triple<T>) -> Self::Output {
+        Triple([self.x() / other.x(), self.y() / other.y(), self.z() / other.z()])
+    }
+}
+
+pub type Point3D = Triple<f64>;
+
+impl Point3D {
     pub fn zero() -> Self {
-        Self([0.0, 0.0])
+        Self([0.0, 0.0, 0.0])
     }
 
     pub fn one() -> Self {
-        Self([1.0, 1.0])
+        Self([1.0, 1.0, 1.0])
     }
 
     pub fn is_nan(&self) -> bool {
-        self.x().is_nan() || self.y().is_nan()
+        self.x().is_nan() || self.y().is_nan() || self.z().is_nan()
     }
 }
 
-impl From<Point2D> for [f64; 2] {
-    fn from(p: Point2D) -> [f64; 2] {
+impl From<Point3D> for [f64; 3] {
+    fn from(p: Point3D) -> [f64; 3] {
         p.0
     }
 }
@@ -153,6 +379,10 @@ impl<S: RTreeObject + PointDistance, T> PointDistance for RTreeObjectWithData<S,
     ) -> <<Self::Envelope as Envelope>::Point as Point>::Scalar {
         self.0.distance_2(point)
     }
+
+    fn contains_point(&self, point: &<Self::Envelope as Envelope>::Point) -> bool {
+        self.0.contains_point(point)
+    }
 }
 
 /// A quadrilateral. Used for interpolation between four points.
@@ -176,55 +406,91 @@ impl<V: Copy + Point> Quad<V> {
     }
 }
 
-impl RTreeObject for Quad<Point2D> {
-    type Envelope = AABB<Point2D>;
+impl<S: Scalar> RTreeObject for Quad<Pair<S>> {
+    type Envelope = AABB<Pair<S>>;
 
     fn envelope(&self) -> Self::Envelope {
         AABB::from_points(self.points.iter())
     }
 }
 
-impl PointDistance for Quad<Point2D> {
-    fn distance_2(&self, point: &Point2D) -> f64 {
-        /// The winding number for a polygon with respect to a point: counts the number of times
-        /// the polygon winds around the point. If the winding number is zero, then the point lies
-        /// outside the polygon.
-        /// This algorithm is based on the one at: http://geomalgorithms.com/a03-_inclusion.html.
-        fn winding_number(point: &Point2D, points: &[Point2D; 4]) -> i8 {
-            // The displacement of a point from a line
-            // (in effect the determinant of a 2x2 matrix).
-            fn displ(line: [Point2D; 2], point: Point2D) -> f64 {
-                let [base, end] = line;
-                let end = end - base;
-                let point = point - base;
-                end.x() * point.y() - end.y() * point.x()
+/// The displacement of a point from a line (in effect the determinant of a 2x2 matrix).
+fn displ<S: Scalar>(line: [Pair<S>; 2], point: Pair<S>) -> S {
+    let [base, end] = line;
+    (end - base).cross(point - base)
+}
+
+impl<S: Scalar> Quad<Pair<S>> {
+    /// The winding number for the quad with respect to a point: counts the number of times the
+    /// quad winds around the point. If the winding number is zero, then the point lies outside the
+    /// quad. Points on the boundary of the quad (including its vertices) may be given either a zero
+    /// or a nonzero winding number, depending on the orientation of the edges they lie on, so they
+    /// should be detected separately using `on_boundary`.
+    /// This algorithm is based on the one at: http://geomalgorithms.com/a03-_inclusion.html.
+    pub fn winding_number(&self, point: &Pair<S>) -> i8 {
+        let points = &self.points;
+        (0..4).map(|i| {
+            let edge = [points[i], points[(i + 1) % 4]];
+            // Count the edges crossing the ray to the right of the point: upwards if the point is
+            // to their left, and downwards if it is to their right.
+            match (edge[0].y() <= point.y(), edge[1].y() <= point.y()) {
+                (true, false) if displ(edge, *point) > S::zero() => 1,
+                (false, true) if displ(edge, *point) < S::zero() => -1,
+                _ => 0,
             }
+        }).sum()
+    }
 
-            (0..4).map(|i| {
-                if (points[i].y() <= point.y()) != (points[(i + 1) % 4].y() <= point.y()) {
-                    match displ([points[i], points[(i + 1) % 4]], *point) {
-                        d if d > 0.0 => 1,
-                        d if d < 0.0 => -1,
-                        _ => 0,
-                    }
-                } else {
-                    0
-                }
-            }).sum()
-        }
+    /// Whether the point lies on one of the edges of the quad (including its vertices), up to
+    /// floating-point error.
+    pub fn on_boundary(&self, point: &Pair<S>) -> bool {
+        // The tolerance of the collinearity test, relative to the lengths of the vectors.
+        let tolerance = S::epsilon() * S::of(4.0);
+        self.edges.iter().any(|edge| {
+            let (along, to_point) = (edge.to - edge.from, *point - edge.from);
+            let length_2 = along.dot(along);
+            if length_2 == S::zero() {
+                // Every point is collinear with a degenerate edge (e.g. between the coincident
+                // vertices where neighbouring normals meet), which is really just a vertex.
+                return to_point.dot(to_point) == S::zero();
+            }
+            // The point lies on the edge if it is collinear with it and between its endpoints.
+            let (cross, projection) = (along.cross(to_point), along.dot(to_point));
+            cross * cross <= tolerance * tolerance * length_2 * to_point.dot(to_point)
+                && projection >= S::zero()
+                && projection <= length_2
+        })
+    }
+
+    /// Whether the point lies within the quad or on its boundary. This agrees with `distance_2`
+    /// being nonpositive, but is cheaper, as it does not compute the distances to the edges.
+    pub fn contains(&self, point: &Pair<S>) -> bool {
+        self.winding_number(point) != 0 || self.on_boundary(point)
+    }
+}
 
+impl<S: Scalar> PointDistance for Quad<Pair<S>> {
+    fn distance_2(&self, point: &Pair<S>) -> S {
         // The minimum distance from any edge to the point.
         let min_dis = self.edges.iter()
-            .filter_map(|edge| OrdFloat::new(edge.distance_2(point)))
+            .filter_map(|edge| OrdFloat::new(edge.distance_2(point).as_f64()))
             .min()
-            .unwrap()
-            .into();
-
-        if winding_number(&point, &self.points) == 0 {
-            min_dis
-        } else {
+            .map(|d| S::of(d.into()))
+            .unwrap();
+
+        if self.on_boundary(point) {
+            // Points on the boundary are treated as inside, though they may have either winding
+            // number, and floating-point error may give them a small distance from their edge.
+            S::zero()
+        } else if self.winding_number(point) != 0 {
             // If the point is contained inside the shape, we must return a negative distance.
             -min_dis
+        } else {
+            min_dis
         }
     }
+
+    fn contains_point(&self, point: &Pair<S>) -> bool {
+        self.contains(point)
+    }
 }
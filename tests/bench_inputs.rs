@@ -0,0 +1,80 @@
+//! Checks that the inputs used by the benchmarks are meaningful, i.e. that every method finds a
+//! reflection for each of them, and that the approximators agree across their entry points.
+
+#[path = "../benches/cases/mod.rs"]
+mod cases;
+
+use reflections::approximation::MirrorSampling;
+use reflections::reflectors::{
+    LinearApproximator, QuadraticApproximator, ViewIndependentApproximator,
+};
+use reflections::spatial::Point2D;
+use reflections::Method;
+
+use crate::cases::{Inputs, CASES, METHODS, STEPS};
+
+#[test]
+fn every_method_reflects_every_case() {
+    for case in &CASES {
+        for &step in &STEPS {
+            let inputs = Inputs::new(case, step);
+            for &(method, threshold) in &METHODS {
+                let points = inputs.reflect(method, threshold);
+                assert!(
+                    points > 0,
+                    "no reflection of {} with step {} using {:?}",
+                    case.name,
+                    step,
+                    method,
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn view_frames_the_cases() {
+    // The view is fitted to the corners of the region without a margin, so the corners lie in the
+    // outermost pixels of the canvas.
+    let view = Inputs::new(&CASES[0], STEPS[0]).view;
+    let region = [view.width as usize, view.height as usize];
+    assert_eq!(view.project(Point2D::diag(-6.0), region), Some([0, 0]));
+    assert_eq!(view.project(Point2D::diag(6.0), region), Some([255, 255]));
+}
+
+#[test]
+fn quadratic_method_does_not_need_view() {
+    let approximator = QuadraticApproximator { mirror_sampling: MirrorSampling::Uniform };
+    for case in &CASES {
+        let inputs = Inputs::new(case, STEPS[0]);
+        let reflection =
+            approximator.approximate_reflection_without_view(inputs.reflection_inputs(), None);
+        assert_eq!(reflection.len(), inputs.reflect(Method::Quadratic, 0.0), "{}", case.name);
+    }
+}
+
+#[test]
+fn straight_figures_are_intersected_exactly() {
+    // In the "line" case, the figure is sampled at the same values of `t` as the mirror, so every
+    // figure point lies on a normal, and the sampled approximation is exact up to rounding.
+    let inputs = Inputs::new(&CASES[0], STEPS[0]);
+    let images = |intersect_straight_figures| {
+        let approximator = LinearApproximator {
+            threshold: 1e-12,
+            mirror_sampling: MirrorSampling::Uniform,
+            intersect_straight_figures,
+        };
+        let mut images: Vec<_> = approximator
+            .approximate_reflection_without_view(inputs.reflection_inputs(), None)
+            .into_iter().map(|(image, _, _)| image.into_inner()).collect();
+        images.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        images
+    };
+    let (exact, sampled) = (images(true), images(false));
+    assert!(!exact.is_empty());
+    assert_eq!(exact.len(), sampled.len());
+    for ([x, y], [sampled_x, sampled_y]) in exact.into_iter().zip(sampled) {
+        assert!((y + 1.0).abs() < 1e-9, "incorrect reflection: ({}, {})", x, y);
+        assert!((x - sampled_x).abs() < 1e-9 && (y - sampled_y).abs() < 1e-9);
+    }
+}
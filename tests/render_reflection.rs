@@ -0,0 +1,507 @@
+//! End-to-end tests of `render_reflection`, the entry point used by the JavaScript client.
+
+use reflections::approximation::{MirrorSampling, View};
+use reflections::parser::{DEFAULT_MAX_DEPTH, DEFAULT_MAX_LENGTH};
+use reflections::reflectors::reflect_across_segment;
+use reflections::spatial::Point2D;
+use reflections::{approximate, Binding, RenderReflectionArgs, Spacing, DEFAULT_MAX_POINTS};
+use reflections::{available_methods, parse_cache_stats, render_all_methods, render_reflection};
+use reflections::Method;
+use serde_json::{json, Value};
+
+/// Each method, paired with a suitable threshold.
+const METHODS: [(&str, f64); 4] =
+    [("rasterisation", 1.0), ("linear", 0.01), ("quadratic", 0.0), ("flat", 0.01)];
+
+/// The arguments to render the reflection of `figure` in a flat mirror along the x-axis, in a view
+/// spanning [-4, 4] in each direction.
+fn args(figure: [&str; 2], sigma_tau: [&str; 2], method: &str, threshold: f64) -> String {
+    json!({
+        "view": { "width": 64, "height": 64, "origin": [0.0, 0.0], "scale": -3.0 },
+        "mirror": ["t", "0"],
+        "figure": figure,
+        "sigma_tau": sigma_tau,
+        "bindings": {
+            "t": { "value": 0.0, "min": -4.0, "max": 4.0, "step": 0.05 },
+            "s": { "value": 0.0, "min": -4.0, "max": 4.0, "step": 0.05 },
+        },
+        "method": method,
+        "threshold": threshold,
+    }).to_string()
+}
+
+/// Render a reflection, returning the image points.
+fn reflection(args: String) -> Vec<[f64; 2]> {
+    let response: Value = serde_json::from_str(&render_reflection(args)).unwrap();
+    assert_eq!(response["ok"], true, "{}", response);
+    response["data"]["reflection"].as_array().unwrap().iter().map(|triple| {
+        serde_json::from_value(triple[0].clone()).unwrap()
+    }).collect()
+}
+
+#[test]
+fn identity_leaves_figure_in_place() {
+    for &(method, threshold) in &METHODS {
+        let images = reflection(args(["0", "t"], ["s", "t"], method, threshold));
+        assert!(!images.is_empty(), "no reflection using {}", method);
+        for [x, _] in images {
+            assert!(x.abs() < 0.2, "image off the figure using {}: {}", method, x);
+        }
+    }
+}
+
+#[test]
+fn omitted_sigma_tau_is_identity() {
+    // The order of the image points is unspecified, so they are sorted before comparison.
+    let sorted = |args: String| {
+        let mut images = reflection(args);
+        images.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        images
+    };
+    for &(method, threshold) in &METHODS {
+        let explicit = sorted(args(["0", "t"], ["s", "t"], method, threshold));
+        let mut args: Value = serde_json::from_str(&args(["0", "t"], ["", ""], method, threshold))
+            .unwrap();
+        assert_eq!(sorted(args.to_string()), explicit, "blank `sigma_tau` using {}", method);
+        args.as_object_mut().unwrap().remove("sigma_tau");
+        assert_eq!(sorted(args.to_string()), explicit, "absent `sigma_tau` using {}", method);
+    }
+}
+
+#[test]
+fn flat_mirror_reflects_across_axis() {
+    // The reflection of the line `y = 1` in the x-axis is the line `y = -1`.
+    for &(method, threshold) in &METHODS {
+        let images = reflection(args(["t", "1"], ["-s", "t"], method, threshold));
+        assert!(!images.is_empty(), "no reflection using {}", method);
+        for [_, y] in images {
+            assert!((y + 1.0).abs() < 0.2, "incorrect reflection using {}: {}", method, y);
+        }
+    }
+}
+
+#[test]
+fn flat_mirror_is_reflected_exactly() {
+    // The figure points lie on the sampled normals, so with a small enough threshold that each is
+    // matched to a single normal, the linear approximation is exact up to rounding. The flat
+    // approximation is exact.
+    let sorted = |method, threshold| {
+        let mut images = reflection(args(["t", "1 + t / 4"], ["-s", "t"], method, threshold));
+        images.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        images
+    };
+    let (flat, linear) = (sorted("flat", 1e-6), sorted("linear", 1e-6));
+    assert_eq!(flat.len(), linear.len());
+    for ([x, y], [linear_x, linear_y]) in flat.into_iter().zip(linear) {
+        assert!((y + 1.0 + x / 4.0).abs() < 1e-12, "incorrect reflection: ({}, {})", x, y);
+        assert!((x - linear_x).abs() < 1e-9 && (y - linear_y).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn diagonal_mirror_matches_reference() {
+    // Each figure point lies on the normal to the mirror `y = x` at the same `t`, so the
+    // approximations are exact up to rounding.
+    let args = |method, threshold| json!({
+        "view": { "width": 64, "height": 64, "origin": [0.0, 0.0], "scale": -3.0 },
+        "mirror": ["t", "t"],
+        "figure": ["t - 0.5", "t + 0.5"],
+        "sigma_tau": ["-s", "t"],
+        "bindings": {
+            "t": { "value": 0.0, "min": -2.0, "max": 2.0, "step": 0.05 },
+            "s": { "value": 0.0, "min": -1.0, "max": 1.0, "step": 0.05 },
+        },
+        "method": method,
+        "threshold": threshold,
+    }).to_string();
+    let sort = |points: &mut Vec<[f64; 2]>| points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    for &(method, threshold) in &[("linear", 1e-6), ("flat", 1e-6)] {
+        let response: Value = serde_json::from_str(&render_reflection(args(method, threshold)))
+            .unwrap();
+        let figure: Vec<Point2D> =
+            serde_json::from_value(response["data"]["figure"].clone()).unwrap();
+        let mut expected: Vec<_> = figure.into_iter().map(|p| {
+            reflect_across_segment(Point2D::zero(), Point2D::one(), p).into_inner()
+        }).collect();
+        let mut images = reflection(args(method, threshold));
+        sort(&mut expected);
+        sort(&mut images);
+
+        assert_eq!(images.len(), expected.len(), "using {}", method);
+        for ([x, y], [expected_x, expected_y]) in images.into_iter().zip(expected) {
+            let error = (x - expected_x).abs().max((y - expected_y).abs());
+            assert!(error < 1e-9, "({}, {}) is not ({}, {})", x, y, expected_x, expected_y);
+        }
+    }
+}
+
+#[test]
+fn translation_along_mirror() {
+    // Translating `t` by `-1` along a flat mirror moves the line `x = 1` to `x = 0`. In particular,
+    // the normal at `t = 1` is translated to `t = 0`.
+    for &(method, threshold) in &METHODS {
+        let images = reflection(args(["1", "t"], ["s", "t - 1"], method, threshold));
+        assert!(!images.is_empty(), "no reflection using {}", method);
+        for [x, _] in images {
+            assert!(x.abs() < 0.2, "incorrect translation using {}: {}", method, x);
+        }
+    }
+}
+
+#[test]
+fn periodic_interval_wraps_around_circle() {
+    // With a step of `0.1`, the last sample of `t` before `2π` is `6.2`. The figure point lies in
+    // the gap between the normals at `6.2` and `2π`.
+    const TAU: f64 = 2.0 * std::f64::consts::PI;
+    let args = |periodic| json!({
+        "view": { "width": 64, "height": 64, "origin": [0.0, 0.0], "scale": -3.0 },
+        "mirror": ["2 * cos(t)", "2 * sin(t)"],
+        "figure": ["3", "-0.05"],
+        "sigma_tau": ["-s", "t"],
+        "bindings": {
+            "t": { "value": 0.0, "min": 0.0, "max": TAU, "step": 0.1, "periodic": periodic },
+            "s": { "value": 0.0, "min": -1.5, "max": 1.5, "step": 0.05 },
+        },
+        "method": "quadratic",
+        "threshold": 0.0,
+    }).to_string();
+
+    assert!(reflection(args(false)).is_empty());
+    let images = reflection(args(true));
+    assert!(!images.is_empty());
+    for [x, y] in images {
+        assert!((x.hypot(y) - 1.0).abs() < 0.01, "incorrect reflection: {:?}", [x, y]);
+    }
+}
+
+#[test]
+fn rasterisation_ignores_nan_images() {
+    // `sigma_tau` is NaN for `s > 0`, but is otherwise the reflection across the x-axis. NaN
+    // coördinates are serialised as `null`, so would fail to deserialise as images.
+    let sigma_tau = ["-s + 0 * acosh(1 - s)", "t"];
+    let images = reflection(args(["t", "-1"], sigma_tau, "rasterisation", 1.0));
+    assert!(!images.is_empty());
+    for [_, y] in images {
+        assert!((y - 1.0).abs() < 0.2, "incorrect reflection: {}", y);
+    }
+}
+
+#[test]
+fn equations_are_parsed_once() {
+    // The cache is thread-local, and each test runs on its own thread.
+    let mut args: Value = serde_json::from_str(&args(["t", "1"], ["-s", "t"], "linear", 0.01))
+        .unwrap();
+    reflection(args.to_string());
+    let first = parse_cache_stats();
+    args["bindings"]["t"]["value"] = json!(0.5);
+    reflection(args.to_string());
+    let second = parse_cache_stats();
+    assert_eq!(second.misses, first.misses, "equations were parsed again");
+    assert_eq!(second.hits, first.hits + 6);
+}
+
+#[test]
+fn reflection_is_clipped_to_view() {
+    // Moving the view to the right leaves only `x` in `[-2, 6]` visible.
+    let mut args: Value = serde_json::from_str(&args(["t", "1"], ["-s", "t"], "linear", 0.01))
+        .unwrap();
+    args["view"]["origin"] = json!([2.0, 0.0]);
+    let unclipped = reflection(args.to_string());
+    args["clip_to_view"] = json!(true);
+    let clipped = reflection(args.to_string());
+    assert!(!clipped.is_empty() && clipped.len() < unclipped.len());
+    for [x, _] in clipped {
+        assert!(x >= -2.0, "image outside view: {}", x);
+    }
+}
+
+#[test]
+fn no_reflection_is_reported() {
+    // The figure lies beyond the normals to the mirror, which span `s` in `[-4, 4]`.
+    for &(method, threshold) in &METHODS {
+        let response: Value = serde_json::from_str(
+            &render_reflection(args(["t", "10"], ["-s", "t"], method, threshold)),
+        ).unwrap();
+        assert_eq!(response["ok"], true, "{}", response);
+        assert_eq!(response["data"]["reflection"], json!([]));
+        assert_eq!(response["data"]["warnings"], json!([{ "kind": "no_reflection" }]));
+    }
+}
+
+#[test]
+fn available_methods_are_implemented() {
+    let names: Vec<_> = METHODS.iter().map(|&(method, _)| method).collect();
+    assert_eq!(available_methods(), &names[..]);
+    for &name in available_methods() {
+        let method: Method = name.parse().unwrap();
+        assert_eq!(method.approximator(1.0, MirrorSampling::Uniform).name(), name);
+    }
+}
+
+#[test]
+fn normals_are_returned_for_each_t() {
+    let mut args: Value = serde_json::from_str(&args(["t", "1"], ["-s", "t"], "linear", 0.01))
+        .unwrap();
+    args["normals"] = json!(true);
+    let response: Value = serde_json::from_str(&render_reflection(args.to_string())).unwrap();
+    let data = &response["data"];
+    let normals: Vec<([f64; 2], [f64; 2])> =
+        serde_json::from_value(data["normals"].clone()).unwrap();
+    // The mirror is sampled at the same values of `t` as the normals.
+    assert_eq!(normals.len(), data["mirror"].as_array().unwrap().len());
+    for ([x0, y0], [x1, y1]) in normals {
+        // The normals to the x-axis are vertical, spanning the interval of `s`.
+        assert!((x0 - x1).abs() < 1e-9, "({}, {}) to ({}, {})", x0, y0, x1, y1);
+        assert!((y0 + 4.0).abs() < 1e-9 && (y1 - 4.0).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn threshold_is_in_pixels_for_every_method() {
+    // The normals are sampled at integer values of `t`, and the figure point lies `0.3` from the
+    // nearest normal, which is between 2 and 3 pixels (of size `0.125`) away.
+    let args = |method, threshold| {
+        let args = args(["0.3", "1"], ["-s", "t"], method, threshold);
+        let mut args: Value = serde_json::from_str(&args).unwrap();
+        args["bindings"]["t"]["step"] = json!(1.0);
+        args.to_string()
+    };
+    for &method in &["rasterisation", "linear"] {
+        assert!(reflection(args(method, 1.0)).is_empty(), "within 1 pixel using {}", method);
+        assert!(!reflection(args(method, 4.0)).is_empty(), "not within 4 pixels using {}", method);
+    }
+}
+
+#[test]
+fn too_many_points_are_reported() {
+    let error = |args: Value| {
+        let response: Value = serde_json::from_str(&render_reflection(args.to_string())).unwrap();
+        assert_eq!(response["ok"], false);
+        response["error"].clone()
+    };
+    let too_many = |points, max_points| json!({
+        "kind": "too_many_points",
+        "detail": format!(
+            "sampling would produce {} points, but at most {} are permitted",
+            points,
+            max_points,
+        ),
+    });
+
+    // Sampling `t` and `s` with this step would produce about 10¹⁶ points, so this must fail
+    // before any are sampled.
+    let mut fine: Value = serde_json::from_str(&args(["t", "1"], ["-s", "t"], "linear", 0.01))
+        .unwrap();
+    for name in &["t", "s"] {
+        fine["bindings"][name]["step"] = json!(1e-7);
+    }
+    // `t` and `s` each have 80,000,001 samples: `s` is sampled for each `t`, as is the figure.
+    let samples: u64 = 80_000_001;
+    assert_eq!(error(fine), too_many(samples * samples + samples, DEFAULT_MAX_POINTS));
+
+    // The budget may also be given explicitly. `t` and `s` each have 161 samples.
+    let mut limited: Value = serde_json::from_str(&args(["t", "1"], ["-s", "t"], "linear", 0.01))
+        .unwrap();
+    limited["max_points"] = json!(1000);
+    assert_eq!(error(limited), too_many(161 * 161 + 161, 1000));
+}
+
+#[test]
+fn every_method_is_rendered() {
+    let response: Value = serde_json::from_str(
+        &render_all_methods(args(["t", "1"], ["-s", "t"], "", 1.0)),
+    ).unwrap();
+    assert_eq!(response["ok"], true, "{}", response);
+    for method in available_methods() {
+        let reflection = response["data"][method]["reflection"].as_array();
+        assert!(!reflection.unwrap().is_empty(), "no reflection using {}", method);
+    }
+}
+
+#[test]
+fn rasterisation_handles_largest_views() {
+    // The view still spans `[-4, 4]` in each direction.
+    let args = |cell_size: f64| {
+        let mut args: Value =
+            serde_json::from_str(&args(["t", "1"], ["-s", "t"], "rasterisation", cell_size))
+                .unwrap();
+        args["view"]["width"] = json!(65535);
+        args["view"]["height"] = json!(65535);
+        args["view"]["scale"] = json!(-13.0);
+        args.to_string()
+    };
+
+    // Rounding the width up to a whole number of cells would overflow a `u16`.
+    let images = reflection(args(16.0));
+    assert!(!images.is_empty());
+    for [_, y] in images {
+        assert!((y + 1.0).abs() < 0.2, "incorrect reflection: {}", y);
+    }
+
+    // A grid of smaller cells would be too large, and is rejected rather than coarsened.
+    let response: Value = serde_json::from_str(&render_reflection(args(8.0))).unwrap();
+    assert_eq!(response["error"], json!({
+        "kind": "invalid_threshold",
+        "detail": "cells of 8 pixels would divide a 65535×65535 pixel view into more than 16777216 \
+            cells",
+    }));
+}
+
+#[test]
+fn reflection_is_deterministic() {
+    // Each `HashMap` is seeded differently, so this would fail if the order of the points depended
+    // on iteration over one.
+    for &(method, threshold) in &METHODS {
+        let args = args(["t / 2", "t^2 / 4 + 1"], ["-s", "t"], method, threshold);
+        let first = render_reflection(args.clone());
+        for _ in 0..4 {
+            assert_eq!(render_reflection(args.clone()), first, "nondeterministic {}", method);
+        }
+    }
+}
+
+#[test]
+fn typed_arguments_match_json() {
+    // The same arguments as `args(["t", "1"], ["-s", "t"], method, threshold)`.
+    let binding = Binding {
+        value: 0.0,
+        min: -4.0,
+        max: 4.0,
+        spacing: Spacing::Step(0.05),
+        periodic: false,
+    };
+    for &(method, threshold) in &METHODS {
+        let data = approximate(RenderReflectionArgs {
+            view: View {
+                width: 64,
+                height: 64,
+                origin: Point2D::zero(),
+                scale: -3.0,
+                scale_mode: Default::default(),
+            },
+            mirror: ["t", "0"],
+            figure: ["t", "1"],
+            sigma_tau: Some(["-s", "t"]),
+            bindings: vec![("t", binding.clone()), ("s", binding.clone())].into_iter().collect(),
+            method,
+            threshold,
+            tolerance: None,
+            mirror_sampling: MirrorSampling::Uniform,
+            clip_to_view: false,
+            max_points: None,
+            normals: false,
+        }).unwrap();
+        // The points may differ in the last place, as `serde_json` does not parse floating-point
+        // numbers exactly.
+        let expected = reflection(args(["t", "1"], ["-s", "t"], method, threshold));
+        assert_eq!(data.reflection.len(), expected.len(), "using {}", method);
+        for ((image, _, _), [x, y]) in data.reflection.into_iter().zip(expected) {
+            let [image_x, image_y] = image.into_inner();
+            assert!((image_x - x).abs() < 1e-12 && (image_y - y).abs() < 1e-12, "using {}", method);
+        }
+    }
+}
+
+#[test]
+fn step_may_be_given_as_resolution() {
+    let mut args: Value = serde_json::from_str(&args(["t", "1"], ["-s", "t"], "linear", 0.01))
+        .unwrap();
+    args["bindings"]["t"] = json!({ "value": 0.0, "min": 0.0, "max": 5.0, "samples_per_unit": 10 });
+    let response: Value = serde_json::from_str(&render_reflection(args.to_string())).unwrap();
+    assert_eq!(response["ok"], true, "{}", response);
+    assert_eq!(response["data"]["figure"].as_array().unwrap().len(), 51);
+
+    args["bindings"]["t"]["samples_per_unit"] = json!(0);
+    let response: Value = serde_json::from_str(&render_reflection(args.to_string())).unwrap();
+    assert_eq!(response["error"], json!({ "kind": "invalid_interval", "detail": "t" }));
+
+    // Exactly one of `step` and `samples_per_unit` must be given. The detail of the error is
+    // followed by the position in the arguments at which deserialisation failed.
+    let assert_invalid = |args: &Value, message| {
+        let response: Value = serde_json::from_str(&render_reflection(args.to_string())).unwrap();
+        assert_eq!(response["error"]["kind"], "invalid_arguments", "{}", response);
+        let detail = response["error"]["detail"].as_str().unwrap();
+        assert!(detail.starts_with(message), "{}", response);
+    };
+    args["bindings"]["t"]["step"] = json!(0.1);
+    assert_invalid(&args, "only one of `step` and `samples_per_unit` may be given at ");
+    args["bindings"]["t"] = json!({ "value": 0.0, "min": 0.0, "max": 5.0 });
+    assert_invalid(&args, "one of `step` and `samples_per_unit` must be given at ");
+}
+
+#[test]
+fn deeply_nested_equation_is_reported() {
+    // Without a limit on nesting, parsing would overflow the stack, aborting the process.
+    let nested = |depth| format!("{}t{}", "(".repeat(depth), ")".repeat(depth));
+    let response: Value = serde_json::from_str(
+        &render_reflection(args([&nested(100), "1"], ["-s", "t"], "linear", 0.01)),
+    ).unwrap();
+    assert_eq!(response["ok"], true, "{}", response);
+    let response: Value = serde_json::from_str(
+        &render_reflection(args([&nested(1000), "1"], ["-s", "t"], "linear", 0.01)),
+    ).unwrap();
+    assert_eq!(response["ok"], false);
+    assert_eq!(response["error"], json!({
+        "kind": "invalid_equation",
+        "detail": format!(
+            "`{}` is nested more than {} levels deep",
+            nested(1000),
+            DEFAULT_MAX_DEPTH,
+        ),
+    }));
+
+    // Chains of operators are not nested, so are limited only by their length.
+    let chain = |length| vec!["t"; length].join(" + ");
+    let response: Value = serde_json::from_str(
+        &render_reflection(args([&chain(200), "1"], ["-s", "t"], "linear", 0.01)),
+    ).unwrap();
+    assert_eq!(response["ok"], true, "{}", response);
+    let response: Value = serde_json::from_str(
+        &render_reflection(args([&chain(2000), "1"], ["-s", "t"], "linear", 0.01)),
+    ).unwrap();
+    assert_eq!(response["error"], json!({
+        "kind": "invalid_equation",
+        "detail": format!(
+            "`{}` has more than {} binary operators",
+            chain(2000),
+            DEFAULT_MAX_LENGTH,
+        ),
+    }));
+}
+
+#[test]
+fn unknown_method_is_reported() {
+    let response: Value = serde_json::from_str(
+        &render_reflection(args(["t", "1"], ["-s", "t"], "cubic", 0.0)),
+    ).unwrap();
+    assert_eq!(response["ok"], false);
+    assert_eq!(response["error"], json!({ "kind": "unknown_method", "detail": "cubic" }));
+}
+
+#[test]
+fn out_of_range_threshold_is_reported() {
+    let error = |method, threshold| {
+        let response: Value = serde_json::from_str(
+            &render_reflection(args(["t", "1"], ["-s", "t"], method, threshold)),
+        ).unwrap();
+        response["error"].clone()
+    };
+    let invalid = |detail: &str| json!({ "kind": "invalid_threshold", "detail": detail });
+    // Cells for rasterisation are at most `u16::MAX` pixels wide, so larger thresholds must not
+    // wrap or saturate silently.
+    assert_eq!(
+        error("rasterisation", 70000.0),
+        invalid("70000 exceeds the largest cell size for rasterisation, 65535"),
+    );
+    assert_eq!(error("rasterisation", 65535.0), Value::Null);
+    assert_eq!(error("linear", 70000.0), Value::Null);
+    for &(method, _) in &METHODS {
+        assert_eq!(
+            error(method, -1.0),
+            invalid("-1 is not a finite, nonnegative number of pixels"),
+            "using {}",
+            method,
+        );
+    }
+}
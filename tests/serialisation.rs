@@ -0,0 +1,88 @@
+//! Tests of the JSON shape of the output of `render_reflection`, which the JavaScript client
+//! depends upon. Renaming a field or changing the nesting of the points would silently break the
+//! client, so the shape is checked explicitly here.
+
+use reflections::spatial::Point2D;
+use reflections::{render_reflection, RenderReflectionData, RenderWarning};
+use serde_json::{json, Value};
+
+/// Render the reflection of the line `y = 1` in the x-axis, returning the JSON response.
+fn response(figure: [&str; 2]) -> Value {
+    let args = json!({
+        "view": { "width": 64, "height": 64, "origin": [0.0, 0.0], "scale": -3.0 },
+        "mirror": ["t", "0"],
+        "figure": figure,
+        "sigma_tau": ["-s", "t"],
+        "bindings": {
+            "t": { "value": 0.0, "min": -4.0, "max": 4.0, "step": 0.5 },
+            "s": { "value": 0.0, "min": -4.0, "max": 4.0, "step": 0.5 },
+        },
+        "method": "quadratic",
+        "threshold": 0.0,
+    });
+    serde_json::from_str(&render_reflection(args.to_string())).unwrap()
+}
+
+/// Whether `value` is a point, i.e. an array of two numbers.
+fn is_point(value: &Value) -> bool {
+    match value.as_array() {
+        Some(pair) => pair.len() == 2 && pair.iter().all(Value::is_number),
+        None => false,
+    }
+}
+
+#[test]
+fn point_is_an_array() {
+    let point = Point2D::new([1.5, -2.0]);
+    let value = serde_json::to_value(point).unwrap();
+    assert_eq!(value, json!([1.5, -2.0]));
+    assert_eq!(serde_json::from_value::<Point2D>(value).unwrap(), point);
+}
+
+#[test]
+fn data_has_expected_shape() {
+    let response = response(["t", "1"]);
+    assert_eq!(response["ok"], true, "{}", response);
+
+    let data = response["data"].as_object().unwrap();
+    let mut fields: Vec<_> = data.keys().map(String::as_str).collect();
+    fields.sort();
+    assert_eq!(fields, ["figure", "mirror", "normals", "reflection", "warnings"]);
+
+    assert!(data["mirror"].as_array().unwrap().iter().all(is_point));
+    assert!(data["figure"].as_array().unwrap().iter().all(is_point));
+    let reflection = data["reflection"].as_array().unwrap();
+    assert!(!reflection.is_empty());
+    for triple in reflection {
+        let triple = triple.as_array().unwrap();
+        assert!(triple.len() == 3 && triple.iter().all(is_point), "{:?}", triple);
+    }
+    assert_eq!(data["warnings"], json!([]));
+    assert_eq!(data["normals"], json!([]));
+}
+
+#[test]
+fn data_round_trips() {
+    let response = response(["t", "1"]);
+    let data: RenderReflectionData = serde_json::from_value(response["data"].clone()).unwrap();
+    assert_eq!(serde_json::to_value(&data).unwrap(), response["data"]);
+}
+
+#[test]
+fn warning_has_kind() {
+    // The figure lies outside the region covered by the normals to the mirror.
+    let response = response(["t", "10"]);
+    assert_eq!(response["data"]["warnings"], json!([{ "kind": "no_reflection" }]));
+    let warnings: Vec<RenderWarning> =
+        serde_json::from_value(response["data"]["warnings"].clone()).unwrap();
+    assert_eq!(warnings, [RenderWarning::NoReflection]);
+}
+
+#[test]
+fn error_has_kind_and_detail() {
+    let response = response(["t", "y"]);
+    assert_eq!(response["ok"], false);
+    let error = response["error"].as_object().unwrap();
+    assert_eq!(error["kind"], "unbound_variables");
+    assert!(error["detail"].is_string());
+}